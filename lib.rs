@@ -10,13 +10,55 @@ mod erc721 {
         HashMap as StorageHashMap,
     };
 
-    use ink_storage::Memory;
+    use ink_env::hash::Blake2x256;
+    use ink_prelude::vec::Vec;
+    use ink_storage::{
+        traits::{
+            PackedLayout,
+            SpreadLayout,
+        },
+        Memory,
+    };
     use scale::{
         Decode,
         Encode,
     };
 
     pub type TokenId = u32;
+    pub type TournamentId = u32;
+    pub type SeasonId = u32;
+    pub type GuildId = u32;
+
+    /// Upper bound (inclusive) of every freshly rolled attribute.
+    const MAX_ROLL: u8 = 16;
+    /// Upper bound (inclusive) of an attribute once ascensions are added on top.
+    const MAX_ATTRIBUTE: u8 = 32;
+    /// Number of affinities, i.e. the size of each side of the advantage matrix.
+    const AFFINITIES: usize = 4;
+    /// Tiers of the hierarchy as returned by `rank_of`, a plain angel being 0.
+    const ARCHANGEL: u8 = 1;
+    const PRINCIPALITY: u8 = 2;
+    const POWER: u8 = 3;
+    const VIRTUE: u8 = 4;
+    const DOMINION: u8 = 5;
+    const THRONE: u8 = 6;
+    const CHERUBIM: u8 = 7;
+    const SERAPHIM: u8 = 8;
+    /// Maximum health gained per point of resilience.
+    const HEALTH_PER_RESILIENCE: u32 = 5;
+    /// Expected score, in per mille, of the higher rated side for rating gaps
+    /// of 0, 50, 100, .. 800 points. Gaps in between are interpolated.
+    const EXPECTED_SCORE: [u32; 17] = [
+        500, 571, 640, 703, 760, 808, 849, 882, 909, 930, 947, 960, 969, 977, 983, 987, 990,
+    ];
+    /// Rating gap covered by each step of `EXPECTED_SCORE`.
+    const RATING_STEP: u32 = 50;
+    /// Raid strength added per tier of rank, on either side.
+    const RAID_RANK_BONUS: u32 = 2;
+    /// Attack gained per stack of Blessed, and defense lost per stack of Wounded.
+    const EFFECT_MODIFIER: u32 = 2;
+    /// Deepest circle of the Fallen hierarchy.
+    const FALLEN_DEPTHS: u8 = 3;
 
     #[ink(storage)]
     pub struct Erc721 {
@@ -45,12 +87,495 @@ mod erc721 {
         throne: StorageHashMap<TokenId, bool>,
         cherubim: StorageHashMap<TokenId, bool>,
         seraphim: StorageHashMap<TokenId, bool>,
-        ///False if one account attacks the other
+        ///True while two tokens are allied, dissolved as soon as one attacks the other.
+        ///Keyed by the lower token id first, so an alliance holds both ways
         alliances: StorageHashMap<(TokenId, TokenId), bool>,
+        ///Pending alliance invitations keyed by (proposer, invitee), valued by the
+        ///first block at which they can no longer be accepted
+        alliance_proposals: StorageHashMap<(TokenId, TokenId), BlockNumber>,
+        ///Tokens each token is allied with
+        allies: StorageHashMap<TokenId, Vec<TokenId>>,
+        ///Status effects on each token, possibly including some that have expired
+        ///but were not pruned yet
+        effects: StorageHashMap<TokenId, Vec<ActiveEffect>>,
+        ///Combat attributes rolled for each token at mint
+        attributes: StorageHashMap<TokenId, Attributes>,
+        ///Affinity assigned to each token at mint
+        affinities: StorageHashMap<TokenId, Affinity>,
+        ///Health left after the last heavy hit and the block it was taken at
+        health: StorageHashMap<TokenId, (u32, BlockNumber)>,
+        ///Elo rating of each token that has fought
+        ratings: StorageHashMap<TokenId, u32>,
+        ///Best tokens by `GameConfig::standing`, best first
+        leaderboard: Vec<TokenId>,
+        ///Ring buffer of recent battles per token, keyed by token and slot
+        history: StorageHashMap<(TokenId, u32), BattleRecord>,
+        ///Number of battles ever recorded per token, the next slot being this modulo the capacity
+        history_count: StorageHashMap<TokenId, u32>,
+        ///Pending duel challenges keyed by (challenger, challenged)
+        challenges: StorageHashMap<(TokenId, TokenId), Challenge>,
+        ///Account collecting the house fees
+        treasury: AccountId,
+        ///Tournaments by id
+        tournaments: StorageHashMap<TournamentId, Tournament>,
+        ///Id the next tournament will get
+        next_tournament: TournamentId,
+        ///Season currently being played
+        season: SeasonId,
+        ///Victories and losses of each token at the end of each finished season
+        season_stats: StorageHashMap<(SeasonId, TokenId), SeasonStats>,
+        ///Season each token's live victories and losses were earned in
+        settled_seasons: StorageHashMap<TokenId, SeasonId>,
+        ///Tiers of rank decay owed by the start of each season, counted from the first
+        season_decay: StorageHashMap<SeasonId, u32>,
+        ///Losses each token has redeemed in each season
+        redemptions: StorageHashMap<(SeasonId, TokenId), u32>,
+        ///Fallen tokens and the victories they have won since they fell
+        fallen: StorageHashMap<TokenId, u32>,
+        ///Losses beyond its victories a redeemed token carried out of the Fall this season
+        forgiven: StorageHashMap<TokenId, u64>,
+        ///Block until which each newly minted token cannot be targeted
+        protection: StorageHashMap<TokenId, BlockNumber>,
+        ///Block each token last acted in and the decay periods it has been charged since
+        activity: StorageHashMap<TokenId, (BlockNumber, u32)>,
+        ///Funds the decay bounties are paid from
+        decay_pool: Balance,
+        ///Token waiting for an opponent in each matchmaking band
+        queue: StorageHashMap<u32, TokenId>,
+        ///Tokens in the matchmaking queue
+        queued: StorageHashMap<TokenId, QueueEntry>,
+        ///Tokens holding each limited rank as their highest
+        seat_holders: StorageHashMap<u8, Vec<TokenId>>,
+        ///Faction war score of each season
+        war_scores: StorageHashMap<SeasonId, FactionScores>,
+        ///Points each token scored for each faction in each season
+        war_contributions: StorageHashMap<(SeasonId, TokenId), FactionScores>,
+        ///Funds paid out to the faction winning the war each season
+        war_chest: Balance,
+        ///Funds set aside for the winning faction of each settled season, and what is
+        ///left of them unclaimed
+        war_pots: StorageHashMap<SeasonId, (Balance, Balance)>,
+        ///Participants that have claimed their share of each season's war pot
+        war_claims: StorageHashMap<(SeasonId, TokenId), bool>,
+        ///Guilds (choirs) by id
+        guilds: StorageHashMap<GuildId, Guild>,
+        ///Guild each token belongs to
+        guild_memberships: StorageHashMap<TokenId, GuildId>,
+        ///Id the next guild will get
+        next_guild: GuildId,
+        ///Guilds each token has been invited to and not joined yet
+        guild_invites: StorageHashMap<TokenId, Vec<GuildId>>,
+        ///Account allowed to change the game configuration
+        admin: AccountId,
+        ///Tunable game rules
+        config: GameConfig,
         ///Experimenting...
         angels: Memory<TokenId>,
     }
 
+    /// Combat attributes of an angel, rolled once when it is minted.
+    #[derive(Encode, Decode, Debug, Default, PartialEq, Eq, Copy, Clone, SpreadLayout, PackedLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub struct Attributes {
+        /// Raw striking strength, added to the attack score.
+        pub might: u8,
+        /// Finesse, added to both the attack and the defense score.
+        pub grace: u8,
+        /// Toughness, added to the defense score.
+        pub resilience: u8,
+        /// Shortens the cooldown after every action.
+        pub speed: u8,
+    }
+
+    /// The choir an angel sings in, deciding its matchups in combat.
+    #[derive(Encode, Decode, Debug, PartialEq, Eq, Copy, Clone, SpreadLayout, PackedLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub enum Affinity {
+        Fire,
+        Light,
+        Storm,
+        Silence,
+    }
+
+    /// The game action a battle was fought with.
+    #[derive(Encode, Decode, Debug, PartialEq, Eq, Copy, Clone, SpreadLayout, PackedLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub enum BattleKind {
+        Attack,
+        ImprovedAttack,
+        GangelBangel,
+        Duel,
+        Tournament,
+        Raid,
+        Dethrone,
+        Matchmaking,
+    }
+
+    /// The side a token fights for.
+    #[derive(Encode, Decode, Debug, PartialEq, Eq, Copy, Clone, SpreadLayout, PackedLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub enum Faction {
+        Heaven,
+        Fallen,
+    }
+
+    /// A temporary condition on a token.
+    #[derive(Encode, Decode, Debug, PartialEq, Eq, Copy, Clone, SpreadLayout, PackedLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub enum StatusEffect {
+        /// Cannot be targeted by attacks or raids.
+        Shielded,
+        /// Cannot attack or raid.
+        Stunned,
+        /// Attacks harder, stacking up to three times.
+        Blessed,
+        /// Defends worse, stacking up to three times.
+        Wounded,
+        /// Cannot be raided. Powers and above are Warded for as long as they hold the rank.
+        Warded,
+    }
+
+    /// A status effect on a token and how long it lasts.
+    #[derive(Encode, Decode, Debug, PartialEq, Eq, Copy, Clone, SpreadLayout, PackedLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub struct ActiveEffect {
+        pub effect: StatusEffect,
+        /// First block at which the effect no longer applies.
+        pub expires_at: BlockNumber,
+        pub stacks: u8,
+    }
+
+    /// A token's record over one season.
+    #[derive(Encode, Decode, Debug, Default, PartialEq, Eq, Copy, Clone, SpreadLayout, PackedLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub struct SeasonStats {
+        pub victories: u32,
+        pub losses: u32,
+    }
+
+    /// Points scored by each faction in battles against the other.
+    #[derive(Encode, Decode, Debug, Default, PartialEq, Eq, Copy, Clone, SpreadLayout, PackedLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub struct FactionScores {
+        pub heaven: u32,
+        pub fallen: u32,
+    }
+
+    /// A choir of angels fighting together.
+    #[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, SpreadLayout, PackedLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub struct Guild {
+        pub leader: TokenId,
+        /// Every member, the leader included, in the order they joined.
+        pub members: Vec<TokenId>,
+    }
+
+    /// Lifecycle of a tournament.
+    #[derive(Encode, Decode, Debug, PartialEq, Eq, Copy, Clone, SpreadLayout, PackedLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub enum TournamentPhase {
+        /// Tokens can still enter.
+        Registration,
+        /// The bracket is seeded and rounds are being fought.
+        Running,
+        /// A champion has been crowned and the prizes paid.
+        Finished,
+        /// Too few tokens entered; the entry fees were refunded.
+        Cancelled,
+    }
+
+    /// A single-elimination tournament.
+    #[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, SpreadLayout, PackedLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub struct Tournament {
+        pub entry_fee: Balance,
+        pub max_entrants: u32,
+        /// First block at which tokens can no longer enter.
+        pub registration_deadline: BlockNumber,
+        pub phase: TournamentPhase,
+        /// Tokens still in the running: in order of entry during registration,
+        /// in seed order afterwards.
+        pub bracket: Vec<TokenId>,
+        /// Knocked out tokens, in the order they fell.
+        pub eliminated: Vec<TokenId>,
+        /// Rounds fought so far.
+        pub round: u32,
+        /// Entry fees collected.
+        pub prize_pool: Balance,
+    }
+
+    /// What the matchmaking queue groups tokens by.
+    #[derive(Encode, Decode, Debug, PartialEq, Eq, Copy, Clone, SpreadLayout, PackedLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub enum Bracket {
+        /// Bands of `rating_band` rating points.
+        Rating,
+        /// One band per rank tier.
+        Rank,
+    }
+
+    /// A token waiting in the matchmaking queue.
+    #[derive(Encode, Decode, Debug, PartialEq, Eq, Copy, Clone, SpreadLayout, PackedLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub struct QueueEntry {
+        /// Account that put the token in the queue.
+        pub owner: AccountId,
+        /// Band the token waits in for an opponent.
+        pub band: u32,
+        /// Block the token entered the queue in.
+        pub entered_at: BlockNumber,
+    }
+
+    /// A duel offered to a token that its owner has not answered yet.
+    #[derive(Encode, Decode, Debug, PartialEq, Eq, Copy, Clone, SpreadLayout, PackedLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub struct Challenge {
+        /// First block at which the challenge can no longer be accepted.
+        pub expires_at: BlockNumber,
+        /// Whether the challenged token forfeits once the challenge expires.
+        pub ranked: bool,
+        /// Value the challenger put in escrow, to be matched by the challenged token.
+        pub stake: Balance,
+        /// Account that put up the stake, and gets it back if the duel is not fought.
+        pub staker: AccountId,
+    }
+
+    /// One battle as seen from one of the tokens that fought it.
+    #[derive(Encode, Decode, Debug, PartialEq, Eq, Copy, Clone, SpreadLayout, PackedLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub struct BattleRecord {
+        pub opponent: TokenId,
+        pub kind: BattleKind,
+        pub won: bool,
+        pub block: BlockNumber,
+    }
+
+    /// Score the leaderboard is ordered by.
+    #[derive(Encode, Decode, Debug, PartialEq, Eq, Copy, Clone, SpreadLayout, PackedLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub enum Standing {
+        Victories,
+        Rating,
+    }
+
+    /// How far apart in rank tokens may fight. Tournament matches are seeded by
+    /// rating alone and ignore the league.
+    #[derive(Encode, Decode, Debug, PartialEq, Eq, Copy, Clone, SpreadLayout, PackedLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub enum League {
+        /// Any token can attack any other.
+        Open,
+        /// Attacks are only allowed within `league_distance` tiers.
+        Bracketed,
+        /// Attacking more than `league_distance` tiers below wins nothing and
+        /// costs `league_penalty` rating.
+        Punitive,
+    }
+
+    /// Game rules the admin can tune after deployment.
+    #[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, SpreadLayout, PackedLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub struct GameConfig {
+        /// Modifier added to the attack score, indexed `[attacker][defender]` by affinity.
+        pub advantages: [[i8; AFFINITIES]; AFFINITIES],
+        /// Blocks the caller waits after `attack`.
+        pub attack_cooldown: u32,
+        /// Blocks the caller waits after `improved_attack`.
+        pub heavy_cooldown: u32,
+        /// Health of a token before resilience is added.
+        pub base_health: u32,
+        /// Blocks it takes to regenerate one point of health, zero heals instantly.
+        pub blocks_per_health: u32,
+        /// Rating every token starts from.
+        pub base_rating: u32,
+        /// Most rating points a single battle can move.
+        pub rating_k: u32,
+        /// Number of tokens kept on the leaderboard.
+        pub leaderboard_size: u32,
+        /// Score the leaderboard is ordered by.
+        pub standing: Standing,
+        /// Number of battles kept in each token's history. Changing it scrambles
+        /// the order of battles already recorded.
+        pub history_size: u32,
+        /// Whether new challenges are ranked, i.e. forfeited by the challenged token if left unanswered.
+        pub ranked_duels: bool,
+        /// Blocks a ranked challenge stays open before it is forfeited. Must be at least 1.
+        pub forfeit_blocks: u32,
+        /// Share of a wagered pot kept by the treasury, in basis points.
+        pub house_fee_bps: u32,
+        /// Shares of a tournament's prize pool paid to the champion, the runner-up and
+        /// the third place, in basis points. Whatever is left goes to the treasury.
+        pub prize_shares_bps: [u32; 3],
+        /// Tiers every token drops when a season ends.
+        pub season_rank_decay: u8,
+        /// Most members a guild can have, the leader included.
+        pub max_guild_size: u32,
+        /// Losses added to a token that attacks its ally.
+        pub betrayal_losses: u32,
+        /// Blocks added to the cooldown of a token that attacks its ally.
+        pub betrayal_cooldown: u32,
+        /// Rating points taken from a token that attacks its ally.
+        pub betrayal_rating_penalty: u32,
+        /// Most tokens that can raid together.
+        pub max_raid_party: u32,
+        /// Blocks every raider's owner waits after a raid.
+        pub raid_cooldown: u32,
+        /// Blocks a token stays Shielded after ascending.
+        pub ascension_shield_blocks: u32,
+        /// Blocks a token stays Wounded after a heavy hit it survived.
+        pub wound_blocks: u32,
+        /// Blocks raiders stay Blessed after a successful raid.
+        pub bless_blocks: u32,
+        /// Blocks a token stays Stunned after being delayed.
+        pub stun_blocks: u32,
+        /// Victories a token spends to redeem one loss.
+        pub redemption_victories: u32,
+        /// Fee paid to the treasury to redeem one loss.
+        pub redemption_fee: Balance,
+        /// Losses a token can redeem in one season.
+        pub redemption_cap: u32,
+        /// Losses over victories past which a token falls, and per circle of the
+        /// Fallen hierarchy it descends after that.
+        pub fall_margin: u32,
+        /// Victories a Fallen token must win to be redeemed.
+        pub redemption_quest: u32,
+        /// Share of the war chest, in basis points, set aside at the end of each season
+        /// for the winning faction's participants to claim. Whatever is still unclaimed
+        /// when the next season ends goes back into the chest.
+        pub war_share_bps: u32,
+        /// Seats for Thrones, Cherubim and Seraphim. Tokens have to dethrone a
+        /// holder to take a full rank.
+        pub seats: [u32; 3],
+        /// Blocks a token can stay idle before its rank starts to decay.
+        pub idle_blocks: u32,
+        /// Blocks of further idleness that cost one more rank tier.
+        pub decay_period: u32,
+        /// Paid from the decay pool to `apply_decay` callers per tier decayed.
+        pub decay_bounty: Balance,
+        /// Blocks a newly minted token cannot be targeted, unless it attacks first.
+        pub protection_blocks: u32,
+        /// League rule applied to every attack.
+        pub league: League,
+        /// Rank tiers apart tokens can be before the league rule applies.
+        pub league_distance: u8,
+        /// Rating lost for attacking too far below under the punitive rule.
+        pub league_penalty: u32,
+        /// What the matchmaking queue pairs tokens by.
+        pub matchmaking: Bracket,
+        /// Width of a matchmaking band when pairing by rating.
+        pub rating_band: u32,
+    }
+
+    impl Default for GameConfig {
+        /// Light dispels Silence, Silence smothers Fire, Fire scatters Storm
+        /// and Storm clouds Light. Opposite choirs are neutral.
+        fn default() -> Self {
+            Self {
+                advantages: [
+                    // Fire, Light, Storm, Silence
+                    [0, 0, 4, -4],
+                    [0, 0, -4, 4],
+                    [-4, 4, 0, 0],
+                    [4, -4, 0, 0],
+                ],
+                attack_cooldown: 7200,
+                heavy_cooldown: 14400,
+                base_health: 100,
+                blocks_per_health: 60,
+                base_rating: 1200,
+                rating_k: 32,
+                leaderboard_size: 10,
+                standing: Standing::Victories,
+                history_size: 16,
+                ranked_duels: false,
+                forfeit_blocks: 14400,
+                house_fee_bps: 250,
+                prize_shares_bps: [6000, 3000, 1000],
+                season_rank_decay: 0,
+                max_guild_size: 8,
+                betrayal_losses: 1,
+                betrayal_cooldown: 7200,
+                betrayal_rating_penalty: 25,
+                max_raid_party: 4,
+                raid_cooldown: 14400,
+                ascension_shield_blocks: 7200,
+                wound_blocks: 7200,
+                bless_blocks: 7200,
+                stun_blocks: 7200,
+                redemption_victories: 2,
+                redemption_fee: 0,
+                redemption_cap: 3,
+                fall_margin: 8,
+                redemption_quest: 8,
+                war_share_bps: 5000,
+                seats: [32, 16, 8],
+                idle_blocks: 201600,
+                decay_period: 100800,
+                decay_bounty: 0,
+                protection_blocks: 14400,
+                league: League::Open,
+                league_distance: 3,
+                league_penalty: 25,
+                matchmaking: Bracket::Rating,
+                rating_band: 100,
+            }
+        }
+    }
+
     #[derive(Encode, Decode, Debug, PartialEq, Eq, Copy, Clone)]
     #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
     pub enum Error {
@@ -62,6 +587,40 @@ mod erc721 {
         CannotRemove,
         CannotFetchValue,
         NotAllowed,
+        ChallengeNotFound,
+        ChallengeExists,
+        ChallengeExpired,
+        InvalidExpiry,
+        InvalidConfig,
+        WrongStake,
+        TransferFailed,
+        TournamentNotFound,
+        TournamentFull,
+        WrongPhase,
+        AlreadyRegistered,
+        GuildNotFound,
+        GuildFull,
+        AlreadyInGuild,
+        NotInGuild,
+        NotInvited,
+        ProposalNotFound,
+        ProposalExpired,
+        Stunned,
+        Shielded,
+        Warded,
+        NoLosses,
+        NotEnoughVictories,
+        InsufficientFee,
+        RedemptionCapReached,
+        Fallen,
+        QuestIncomplete,
+        RankFull,
+        Protected,
+        OutOfLeague,
+        AlreadyQueued,
+        NotQueued,
+        NoWarReward,
+        AlreadyClaimed,
     }
 
     /// Event emitted when a token transfer occurs.
@@ -105,14 +664,243 @@ mod erc721 {
         victories: u64,
     }
 
+    /// Event emitted when a fight between two tokens is resolved.
+    #[ink(event)]
+    pub struct Battle {
+        #[ink(topic)]
+        attacker: TokenId,
+        #[ink(topic)]
+        defender: TokenId,
+        kind: BattleKind,
+        attacker_won: bool,
+        /// Affinity modifier that was added to the attack score.
+        advantage: i8,
+        attacker_rating_delta: i32,
+        defender_rating_delta: i32,
+    }
+
+    /// Event emitted when a heavy attack wounds a token without knocking it down.
+    #[ink(event)]
+    pub struct Strike {
+        #[ink(topic)]
+        attacker: TokenId,
+        #[ink(topic)]
+        victim: TokenId,
+        damage: u32,
+        health: u32,
+    }
+
+    /// Event emitted when a token challenges another to a duel.
+    #[ink(event)]
+    pub struct ChallengeIssued {
+        #[ink(topic)]
+        challenger: TokenId,
+        #[ink(topic)]
+        challenged: TokenId,
+        expires_at: BlockNumber,
+        ranked: bool,
+        stake: Balance,
+    }
+
+    /// Event emitted when the challenged token takes up a duel, just before it is fought.
+    #[ink(event)]
+    pub struct ChallengeAccepted {
+        #[ink(topic)]
+        challenger: TokenId,
+        #[ink(topic)]
+        challenged: TokenId,
+    }
+
+    /// Event emitted when the challenged token turns a duel down.
+    #[ink(event)]
+    pub struct ChallengeDeclined {
+        #[ink(topic)]
+        challenger: TokenId,
+        #[ink(topic)]
+        challenged: TokenId,
+    }
+
+    /// Event emitted when the pot of a wagered duel is paid out.
+    #[ink(event)]
+    pub struct WagerSettled {
+        #[ink(topic)]
+        winner: TokenId,
+        prize: Balance,
+        fee: Balance,
+    }
+
+    /// Event emitted when the admin opens a tournament.
+    #[ink(event)]
+    pub struct TournamentCreated {
+        #[ink(topic)]
+        tournament: TournamentId,
+        entry_fee: Balance,
+        max_entrants: u32,
+        registration_deadline: BlockNumber,
+    }
+
+    /// Event emitted when a token enters a tournament.
+    #[ink(event)]
+    pub struct TournamentEntered {
+        #[ink(topic)]
+        tournament: TournamentId,
+        #[ink(topic)]
+        token: TokenId,
+    }
+
+    /// Event emitted when registration closes and the bracket is seeded.
+    #[ink(event)]
+    pub struct TournamentSeeded {
+        #[ink(topic)]
+        tournament: TournamentId,
+        bracket: Vec<TokenId>,
+    }
+
+    /// Event emitted when a round of a tournament has been fought.
+    #[ink(event)]
+    pub struct TournamentRound {
+        #[ink(topic)]
+        tournament: TournamentId,
+        round: u32,
+        advancing: Vec<TokenId>,
+    }
+
+    /// Event emitted when a tournament ends, with no champion if it was cancelled.
+    #[ink(event)]
+    pub struct TournamentFinished {
+        #[ink(topic)]
+        tournament: TournamentId,
+        champion: Option<TokenId>,
+        prize_pool: Balance,
+    }
+
+    /// Event emitted when a token founds a guild.
+    #[ink(event)]
+    pub struct GuildCreated {
+        #[ink(topic)]
+        guild: GuildId,
+        #[ink(topic)]
+        leader: TokenId,
+    }
+
+    /// Event emitted when a guild's leader invites a token to join.
+    #[ink(event)]
+    pub struct GuildInvited {
+        #[ink(topic)]
+        guild: GuildId,
+        #[ink(topic)]
+        token: TokenId,
+    }
+
+    /// Event emitted when a token joins a guild.
+    #[ink(event)]
+    pub struct GuildJoined {
+        #[ink(topic)]
+        guild: GuildId,
+        #[ink(topic)]
+        token: TokenId,
+    }
+
+    /// Event emitted when a token leaves a guild or is kicked out of it.
+    #[ink(event)]
+    pub struct GuildLeft {
+        #[ink(topic)]
+        guild: GuildId,
+        #[ink(topic)]
+        token: TokenId,
+        kicked: bool,
+    }
+
+    /// Event emitted when a token takes or gives up a seat of a limited rank.
+    #[ink(event)]
+    pub struct SeatChanged {
+        #[ink(topic)]
+        token: TokenId,
+        #[ink(topic)]
+        rank: u8,
+        seated: bool,
+    }
+
+    /// Event emitted when an idle token loses rank.
+    #[ink(event)]
+    pub struct Decayed {
+        #[ink(topic)]
+        token: TokenId,
+        from: u8,
+        to: u8,
+    }
+
+    /// Event emitted when a token starts waiting for an opponent.
     #[ink(event)]
-    pub struct Attack {
+    pub struct Queued {
         #[ink(topic)]
-        attacker: AccountId,
+        token: TokenId,
+        band: u32,
+    }
+
+    /// Event emitted when a token stops waiting without having been matched.
+    #[ink(event)]
+    pub struct LeftQueue {
+        #[ink(topic)]
+        token: TokenId,
+    }
+
+    /// Event emitted when a season's faction war is settled.
+    #[ink(event)]
+    pub struct WarEnded {
+        #[ink(topic)]
+        season: SeasonId,
+        /// Faction with the higher score, none on a tie.
+        winner: Option<Faction>,
+        scores: FactionScores,
+        pot: Balance,
+    }
+
+    /// Event emitted when a participant claims its share of a war pot.
+    #[ink(event)]
+    pub struct WarRewardClaimed {
+        #[ink(topic)]
+        season: SeasonId,
+        #[ink(topic)]
+        token: TokenId,
+        reward: Balance,
+    }
+
+    /// Event emitted when a season ends and the next one starts.
+    #[ink(event)]
+    pub struct SeasonEnded {
+        #[ink(topic)]
+        season: SeasonId,
+    }
+
+    /// Event emitted when an unanswered challenge is cleared after it expired.
+    #[ink(event)]
+    pub struct ChallengeExpired {
+        #[ink(topic)]
+        challenger: TokenId,
+        #[ink(topic)]
+        challenged: TokenId,
+        /// True if the challenge was ranked and the challenged token lost by forfeit.
+        forfeited: bool,
+    }
+
+    /// Event emitted when a token invites another into an alliance.
+    #[ink(event)]
+    pub struct AllianceProposed {
+        #[ink(topic)]
+        angel: TokenId,
+        #[ink(topic)]
+        ally: TokenId,
+        expires_at: BlockNumber,
+    }
+
+    /// Event emitted when the invited token turns an alliance down.
+    #[ink(event)]
+    pub struct AllianceRejected {
         #[ink(topic)]
-        victim: AccountId,
+        angel: TokenId,
         #[ink(topic)]
-        block: BlockNumber,
+        ally: TokenId,
     }
 
     #[ink(event)]
@@ -123,6 +911,95 @@ mod erc721 {
         ally: TokenId,
     }
 
+    /// Event emitted when a status effect is put on a token or stacked again.
+    #[ink(event)]
+    pub struct EffectApplied {
+        #[ink(topic)]
+        token: TokenId,
+        effect: StatusEffect,
+        expires_at: BlockNumber,
+        stacks: u8,
+    }
+
+    /// Event emitted when an expired status effect is cleared from a token.
+    #[ink(event)]
+    pub struct EffectExpired {
+        #[ink(topic)]
+        token: TokenId,
+        effect: StatusEffect,
+    }
+
+    /// Event emitted when a party of allied tokens raids a victim.
+    #[ink(event)]
+    pub struct Raid {
+        #[ink(topic)]
+        victim: TokenId,
+        participants: Vec<TokenId>,
+        kind: BattleKind,
+        success: bool,
+        /// Rating points each participant gained, or lost if the raid failed.
+        rating_share: u32,
+    }
+
+    /// Event emitted when a token buys back one of its losses.
+    #[ink(event)]
+    pub struct LossErased {
+        #[ink(topic)]
+        token: TokenId,
+        season: SeasonId,
+        victories_spent: u32,
+        fee: Balance,
+    }
+
+    /// Event emitted when a token's losses outweigh its victories and it falls.
+    #[ink(event)]
+    pub struct Fell {
+        #[ink(topic)]
+        token: TokenId,
+        victories: u32,
+        losses: u32,
+        rank: u8,
+    }
+
+    /// Event emitted when a Fallen token completes its quest and returns to Heaven.
+    #[ink(event)]
+    pub struct Redeemed {
+        #[ink(topic)]
+        token: TokenId,
+        season: SeasonId,
+    }
+
+    /// Event emitted when an archangel stuns another token.
+    #[ink(event)]
+    pub struct Delayed {
+        #[ink(topic)]
+        from: TokenId,
+        #[ink(topic)]
+        target: TokenId,
+        until: BlockNumber,
+    }
+
+    /// Event emitted when a token attacks its ally, breaking the alliance.
+    #[ink(event)]
+    pub struct Betrayal {
+        #[ink(topic)]
+        betrayer: TokenId,
+        #[ink(topic)]
+        betrayed: TokenId,
+        losses: u32,
+        rating_penalty: u32,
+        cooldown: u32,
+    }
+
+    /// Event emitted when an alliance ends.
+    #[ink(event)]
+    pub struct AllianceDissolved {
+        #[ink(topic)]
+        angel: TokenId,
+        #[ink(topic)]
+        ally: TokenId,
+    }
+
     ///Public functions
     impl Erc721 {
         /// Creates a new ERC721 token contract.
@@ -146,6 +1023,44 @@ mod erc721 {
                 cherubim: Default::default(),
                 seraphim: Default::default(),
                 alliances: Default::default(),
+                alliance_proposals: Default::default(),
+                allies: Default::default(),
+                effects: Default::default(),
+                attributes: Default::default(),
+                affinities: Default::default(),
+                health: Default::default(),
+                ratings: Default::default(),
+                leaderboard: Default::default(),
+                history: Default::default(),
+                history_count: Default::default(),
+                challenges: Default::default(),
+                treasury: Self::env().caller(),
+                tournaments: Default::default(),
+                next_tournament: 0,
+                season: 0,
+                season_stats: Default::default(),
+                settled_seasons: Default::default(),
+                season_decay: Default::default(),
+                redemptions: Default::default(),
+                fallen: Default::default(),
+                forgiven: Default::default(),
+                protection: Default::default(),
+                activity: Default::default(),
+                decay_pool: 0,
+                queue: Default::default(),
+                queued: Default::default(),
+                seat_holders: Default::default(),
+                war_scores: Default::default(),
+                war_contributions: Default::default(),
+                war_chest: 0,
+                war_pots: Default::default(),
+                war_claims: Default::default(),
+                guilds: Default::default(),
+                guild_memberships: Default::default(),
+                next_guild: 0,
+                guild_invites: Default::default(),
+                admin: Self::env().caller(),
+                config: Default::default(),
                 angels: Default::default(),
             }
         }
@@ -172,43 +1087,156 @@ mod erc721 {
         pub fn losses_count(&self, owner: TokenId) -> u64 {
             self.losses_of_or_zero(&owner)
         }
-        ///Getters of credibility status
+        ///Getters of credibility status, counting season decay not yet applied
         #[ink(message)]
         pub fn is_archangel(&self, token: TokenId) -> bool {
-            self.archangel(token)
+            self.archangel(token) && self.effective_rank(token) >= ARCHANGEL
         }
         #[ink(message)]
         pub fn is_principality(&self, token: TokenId) -> bool {
-            self.principality(token)
+            self.principality(token) && self.effective_rank(token) >= PRINCIPALITY
         }
         #[ink(message)]
         pub fn is_power(&self, token: TokenId) -> bool {
-            self.power(token)
+            self.power(token) && self.effective_rank(token) >= POWER
         }
         #[ink(message)]
         pub fn is_virtue(&self, token: TokenId) -> bool {
-            self.virtue(token)
+            self.virtue(token) && self.effective_rank(token) >= VIRTUE
         }
         #[ink(message)]
         pub fn is_dominion(&self, token: TokenId) -> bool {
-            self.dominion(token)
+            self.dominion(token) && self.effective_rank(token) >= DOMINION
         }
         #[ink(message)]
         pub fn is_throne(&self, token:TokenId) -> bool {
-            self.throne(token)
+            self.throne(token) && self.effective_rank(token) >= THRONE
         }
         #[ink(message)]
         pub fn is_cherubim(&self, token: TokenId) -> bool {
-            self.cherubim(token)
+            self.cherubim(token) && self.effective_rank(token) >= CHERUBIM
         }
         #[ink(message)]
         pub fn is_seraphim(&self, token: TokenId) -> bool {
-            self.seraphim(token)
+            self.seraphim(token) && self.effective_rank(token) >= SERAPHIM
+        }
+        ///Returns the Elo rating of a token, the base rating if it has never fought
+        #[ink(message)]
+        pub fn rating_of(&self, token: TokenId) -> u32 {
+            self.rating(token)
+        }
+        ///Returns the top tokens with their scores, best first. Ties go to the lower id
+        #[ink(message)]
+        pub fn leaderboard(&self) -> Vec<(TokenId, u32)> {
+            self.leaderboard
+                .iter()
+                .map(|id| (*id, self.standing(*id)))
+                .collect()
+        }
+        ///Offers tokens for the leaderboard. The board is only updated as tokens fight,
+        ///so anyone can use this to fill places left by burnt or outranked members
+        #[ink(message)]
+        pub fn refresh_leaderboard(&mut self, ids: Vec<TokenId>) {
+            for id in ids {
+                if self.exists(id) {
+                    self.update_leaderboard(id);
+                };
+            }
+        }
+        ///Returns up to `limit` of the token's recorded battles, newest first,
+        ///skipping the `offset` most recent ones
+        #[ink(message)]
+        pub fn battle_history(&self, id: TokenId, offset: u32, limit: u32) -> Vec<BattleRecord> {
+            let count = *self.history_count.get(&id).unwrap_or(&0);
+            let kept = core::cmp::min(count, self.config.history_size);
+            (offset..core::cmp::min(offset.saturating_add(limit), kept))
+                .filter_map(|back| {
+                    let slot = (count - 1 - back) % self.config.history_size;
+                    self.history.get(&(id, slot)).cloned()
+                })
+                .collect()
+        }
+        ///Returns the highest tier of the hierarchy the token holds, 0 for a plain angel,
+        ///counting season decay not yet applied
+        #[ink(message)]
+        pub fn rank_of(&self, token: TokenId) -> u8 {
+            self.effective_rank(token)
         }
         pub fn is_ready(&self, account: AccountId) -> BlockNumber {
             self.ready(account)
         }
 
+        /// Returns the combat attributes of the token, if it exists.
+        #[ink(message)]
+        pub fn attributes_of(&self, id: TokenId) -> Option<Attributes> {
+            self.attributes.get(&id).cloned()
+        }
+
+        /// Returns the affinity of the token, if it exists.
+        #[ink(message)]
+        pub fn affinity_of(&self, id: TokenId) -> Option<Affinity> {
+            self.affinities.get(&id).cloned()
+        }
+
+        /// Returns the current health of the token, regenerated up to the current block.
+        #[ink(message)]
+        pub fn health_of(&self, id: TokenId) -> Option<u32> {
+            if !self.exists(id) {
+                return None
+            };
+            Some(self.current_health(id))
+        }
+
+        /// Returns the status effects currently on the token.
+        #[ink(message)]
+        pub fn effects_of(&self, id: TokenId) -> Vec<ActiveEffect> {
+            let block = self.env().block_number();
+            self.effects
+                .get(&id)
+                .map(|effects| {
+                    effects
+                        .iter()
+                        .filter(|active| active.expires_at > block)
+                        .cloned()
+                        .collect()
+                })
+                .unwrap_or_default()
+        }
+
+        /// Returns the modifier an `attacker` of one affinity gets against a `defender` of another.
+        #[ink(message)]
+        pub fn advantage_of(&self, attacker: Affinity, defender: Affinity) -> i8 {
+            self.config.advantages[attacker as usize][defender as usize]
+        }
+
+        /// Returns the current game configuration.
+        #[ink(message)]
+        pub fn config(&self) -> GameConfig {
+            self.config.clone()
+        }
+
+        /// Replaces the game configuration. Only the admin can do this.
+        #[ink(message)]
+        pub fn set_config(&mut self, config: GameConfig) -> Result<(), Error> {
+            if self.env().caller() != self.admin {
+                return Err(Error::NotAllowed)
+            };
+            if config.house_fee_bps > 10_000
+                || config.prize_shares_bps.iter().sum::<u32>() > 10_000
+                || config.war_share_bps > 10_000
+                || config.forfeit_blocks == 0
+            {
+                return Err(Error::InvalidConfig)
+            };
+            let reorder = config.leaderboard_size != self.config.leaderboard_size
+                || config.standing != self.config.standing;
+            self.config = config;
+            if reorder {
+                self.reorder_leaderboard();
+            };
+            Ok(())
+        }
+
         /// Returns the owner of the token.
         #[ink(message)]
         pub fn owner_of(&self, id: TokenId) -> Option<AccountId> {
@@ -216,28 +1244,39 @@ mod erc721 {
         }
 
         
-        ///Prototype for the actions a player can execute against another player
+        ///Quick attack: a single exchange that immediately records a victory and a loss
         #[ink(message, payable)]
         pub fn attack(&mut self, from: TokenId, to: TokenId) -> Result<(), Error> {
             let caller = self.env().caller();
             if self.is_account_allowed(caller) == false {
                 return Err(Error::NotAllowed)
             };
-            self.add_loss(&to);
-            self.add_victory(&from);
-            self.time_constrain(caller, 7200);     
+            self.ensure_combatants(caller, from, to)?;
+            self.ensure_can_strike(from, to)?;
+            let penalty = self.punish_betrayal(from, to);
+            self.fight(BattleKind::Attack, from, to);
+            let cooldown = self.cooldown_of(from, self.config.attack_cooldown);
+            self.time_constrain(caller, cooldown + penalty);
             Ok(())
         }
 
+        ///Heavy attack for Principalities and above. Deals damage to the victim's health
+        ///and only records a loss once the victim is worn down to zero
         #[ink(message, payable)]
         pub fn improved_attack(&mut self, attacker: TokenId, victim: TokenId) -> Result<(), Error> {
             let caller = self.env().caller();
             if self.is_account_allowed(caller) == false {
                 return Err(Error::NotAllowed)
             };
-            self.add_loss(&victim);
-            self.add_victory(&attacker);
-            self.time_constrain(caller, 7200);
+            self.ensure_combatants(caller, attacker, victim)?;
+            if self.rank(attacker) < PRINCIPALITY {
+                return Err(Error::NotAllowed)
+            };
+            self.ensure_can_strike(attacker, victim)?;
+            let penalty = self.punish_betrayal(attacker, victim);
+            self.strike(attacker, victim);
+            let cooldown = self.cooldown_of(attacker, self.config.heavy_cooldown);
+            self.time_constrain(caller, cooldown + penalty);
             Ok(())
         }
 
@@ -249,42 +1288,102 @@ mod erc721 {
             if self.is_account_allowed(caller) == false {
                 return Err(Error::NotAllowed)
             };
+            if self.fallen.contains_key(&id) {
+                return Err(Error::Fallen)
+            };
             let vict_count = self.victories_count(id);
-            match vict_count {
-                4 => self.archangel.insert(id, true),
-                8 => self.principality.insert(id, true),
-                16 => self.power.insert(id, true),
-                32 => self.virtue.insert(id, true),
-                64 => self.dominion.insert(id, true),
-                128 => self.throne.insert(id, true),
-                256 => self.cherubim.insert(id, true),
-                512 => self.seraphim.insert(id, true),
+            let tier = match vict_count {
+                4 => ARCHANGEL,
+                8 => PRINCIPALITY,
+                16 => POWER,
+                32 => VIRTUE,
+                64 => DOMINION,
+                128 => THRONE,
+                256 => CHERUBIM,
+                512 => SERAPHIM,
                 _ => return Err(Error::NotAllowed),
             };
+            self.touch(id);
+            let previous = self.rank(id);
+            if previous < tier && self.seats_full(tier) {
+                return Err(Error::RankFull)
+            };
+            if tier > previous {
+                self.set_rank(id, tier);
+            };
+            //Every ascension hardens the angel a little and shields it for a while
+            if let Some(attributes) = self.attributes.get_mut(&id) {
+                *attributes = attributes.ascended();
+            }
+            self.apply_effect(id, StatusEffect::Shielded, self.config.ascension_shield_blocks);
             self.env().emit_event(Ascension {
                 token: id,
                 victories: vict_count,
             });
-            self.time_constrain(caller, 7200);
+            let cooldown = self.cooldown_of(id, 7200);
+            self.time_constrain(caller, cooldown);
             Ok(())
         }
 
+        ///Buys back one loss of the caller's token. It costs `redemption_victories`
+        ///victories and a `redemption_fee` paid to the treasury, and a token can only
+        ///redeem `redemption_cap` losses per season. Anything sent beyond the fee is refunded
         #[ink(message, payable)]
         pub fn erase_loss(&mut self, id: TokenId) -> Result<(), Error> {
             let caller = self.env().caller();
             if self.is_account_allowed(caller) == false {
                 return Err(Error::NotAllowed)
             };
-            assert!(self.victories_count(id) > 63, "Must have at least 64 victories");
+            if self.owner_of(id) != Some(caller) {
+                return Err(Error::NotOwner)
+            };
+            self.settle_season(id);
+            if self.losses_count(id) == 0 {
+                return Err(Error::NoLosses)
+            };
+            let redeemed = self.redemptions_of(self.season, id);
+            if redeemed >= self.config.redemption_cap {
+                return Err(Error::RedemptionCapReached)
+            };
+            let price = self.config.redemption_victories;
+            if self.victories_count(id) < u64::from(price) {
+                return Err(Error::NotEnoughVictories)
+            };
+            let paid = self.env().transferred_balance();
+            let fee = self.config.redemption_fee;
+            if paid < fee {
+                return Err(Error::InsufficientFee)
+            };
+            let treasury = self.treasury;
+            self.pay(treasury, fee)?;
+            self.pay(caller, paid - fee)?;
+            self.touch(id);
             let Self {
                 losses,
                 ..
             } = self;
             decrease_counter_of_tokenid(losses, &id)?;
+            if let Some(victories) = self.victories.get_mut(&id) {
+                *victories -= price;
+            }
+            self.redemptions.insert((self.season, id), redeemed + 1);
+            self.update_leaderboard(id);
+            self.env().emit_event(LossErased {
+                token: id,
+                season: self.season,
+                victories_spent: price,
+                fee,
+            });
             self.time_constrain(caller, 7200);
             Ok(())
         }
 
+        ///Returns how many losses the token has redeemed in the given season.
+        #[ink(message)]
+        pub fn redemptions_of(&self, season: SeasonId, id: TokenId) -> u32 {
+            self.redemptions.get(&(season, id)).copied().unwrap_or(0)
+        }
+
         ///Seraphims can remove the status of archangels to simple angels
         #[ink(message, payable)]
         pub fn relegate_archangel(&mut self, id: TokenId, to: TokenId) -> Result<(), Error> {
@@ -302,29 +1401,116 @@ mod erc721 {
             Ok(())
         }
 
+        ///Stuns the `target` token so it cannot attack for a while. Its owner's other
+        ///tokens are unaffected
         #[ink(message)]
-        pub fn delay_angel(&mut self, from: TokenId, opponent: AccountId) -> Result<(), Error> {
-            assert!(self.is_archangel(from) == true, "Only Archangels are allowed");
+        pub fn delay_angel(&mut self, from: TokenId, target: TokenId) -> Result<(), Error> {
             let caller = self.env().caller();
-            if self.is_account_allowed(caller) == false {
+            if self.is_account_allowed(caller) == false || self.is_archangel(from) == false {
                 return Err(Error::NotAllowed)
             };
-            self.time_constrain(opponent, 7200);
+            self.ensure_combatants(caller, from, target)?;
+            self.ensure_can_strike(from, target)?;
+            self.engage(from, target);
+            let until = self.apply_effect(target, StatusEffect::Stunned, self.config.stun_blocks);
+            self.env().emit_event(Delayed {
+                from,
+                target,
+                until,
+            });
             self.time_constrain(caller, 3600);
             Ok(())
         }
 
+        ///Invites `ally` into an alliance with the caller's `angel`. The alliance only
+        ///forms once the ally's owner accepts, within `expiry` blocks
         #[ink(message, payable)]
-        pub fn form_alliance(&mut self, angel: TokenId, ally: TokenId) -> Result<(), Error> {
+        pub fn propose_alliance(
+            &mut self,
+            angel: TokenId,
+            ally: TokenId,
+            expiry: BlockNumber,
+        ) -> Result<(), Error> {
             let caller = self.env().caller();
             if self.is_account_allowed(caller) == false {
                 return Err(Error::NotAllowed)
             };
-            self.ally(angel, ally, true)?;
+            self.ensure_combatants(caller, angel, ally)?;
+            if angel == ally {
+                return Err(Error::NotAllowed)
+            };
+            if expiry == 0 {
+                return Err(Error::InvalidExpiry)
+            };
+            let expires_at = self.env().block_number().saturating_add(expiry);
+            self.touch(angel);
+            self.alliance_proposals.insert((angel, ally), expires_at);
+            self.env().emit_event(AllianceProposed {
+                angel,
+                ally,
+                expires_at,
+            });
             self.time_constrain(caller, 7200);
             Ok(())
         }
 
+        ///Forms the alliance `angel` proposed. Only the owner of `ally` can accept
+        #[ink(message, payable)]
+        pub fn accept_alliance(&mut self, angel: TokenId, ally: TokenId) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if self.owner_of(ally) != Some(caller) {
+                return Err(Error::NotOwner)
+            };
+            let expires_at = *self
+                .alliance_proposals
+                .get(&(angel, ally))
+                .ok_or(Error::ProposalNotFound)?;
+            if self.env().block_number() >= expires_at {
+                return Err(Error::ProposalExpired)
+            };
+            self.ally(angel, ally, true)?;
+            self.alliance_proposals.take(&(angel, ally));
+            self.touch(ally);
+            Ok(())
+        }
+
+        ///Turns down, or clears once expired, the alliance `angel` proposed.
+        ///Only the owner of `ally` can reject
+        #[ink(message)]
+        pub fn reject_alliance(&mut self, angel: TokenId, ally: TokenId) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if self.owner_of(ally) != Some(caller) {
+                return Err(Error::NotOwner)
+            };
+            self.alliance_proposals
+                .take(&(angel, ally))
+                .ok_or(Error::ProposalNotFound)?;
+            self.env().emit_event(AllianceRejected { angel, ally });
+            Ok(())
+        }
+
+        ///Ends an alliance the caller's `angel` is part of
+        #[ink(message)]
+        pub fn break_alliance(&mut self, angel: TokenId, ally: TokenId) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if self.owner_of(angel) != Some(caller) {
+                return Err(Error::NotOwner)
+            };
+            self.ally(angel, ally, false)
+        }
+
+        ///Returns every token allied with `token`
+        #[ink(message)]
+        pub fn allies_of(&self, token: TokenId) -> Vec<TokenId> {
+            self.allies.get(&token).cloned().unwrap_or_default()
+        }
+
+        ///Returns the block at which the alliance `angel` proposed to `ally` expires, if pending
+        #[ink(message)]
+        pub fn alliance_proposal(&self, angel: TokenId, ally: TokenId) -> Option<BlockNumber> {
+            self.alliance_proposals.get(&(angel, ally)).cloned()
+        }
+
         #[ink(message, payable)]
         pub fn dissolve_alliance(&mut self, attacker: TokenId, victim: TokenId, ally: TokenId) -> Result<(), Error> {
             let caller = self.env().caller();
@@ -337,28 +1523,179 @@ mod erc721 {
             Ok(())
         }
         
+        ///A raid by exactly two comrades
         #[ink(message, payable)]
         pub fn gangel_bangel(
             &mut self,
             attacker: TokenId, 
             attacker_ally: TokenId, 
             victim: TokenId) -> Result<(), Error> {
-                let caller = self.env().caller();
-                if self.is_account_allowed(caller) == false {
-                    return Err(Error::NotAllowed)
-                };
-                assert!(self.is_allied(attacker, attacker_ally) == true, "Only alliances allowed");
-                assert!(self.is_power(victim) != true, "Powers are immune");
-            self.add_loss(&victim);
-            let Self {
-                victories,
-                ..
-            } = self;
-            decrease_counter_of_tokenid(victories, &victim)?;
-            self.time_constrain(caller, 14400);
+            let mut party = Vec::new();
+            party.push(attacker);
+            party.push(attacker_ally);
+            self.lead_raid(BattleKind::GangelBangel, party, victim)
+        }
+
+        ///Attacks `victim` with a party led by the caller's first token. Every raider must be
+        ///allied or guilded with every other, and every raider's owner must be off cooldown.
+        ///The party's combined strength, grown by rank, is matched against the victim's
+        ///defense, grown by rank and party size. On success the victim takes a loss per
+        ///raider and every raider a victory; either way the rating at stake is shared among
+        ///the raiders. Raiding an ally is a betrayal, and Warded tokens, Powers and above
+        ///among them, cannot be raided
+        #[ink(message, payable)]
+        pub fn raid(&mut self, attackers: Vec<TokenId>, victim: TokenId) -> Result<(), Error> {
+            self.lead_raid(BattleKind::Raid, attackers, victim)
+        }
+
+        ///Challenges `defender` to a duel its owner can accept or decline within `expiry`
+        ///blocks. In ranked mode the window is `forfeit_blocks` and silence is a forfeit.
+        ///Any value sent is escrowed as the challenger's stake. Challenging costs the caller
+        ///the same cooldown as an attack
+        #[ink(message, payable)]
+        pub fn challenge(
+            &mut self,
+            attacker: TokenId,
+            defender: TokenId,
+            expiry: BlockNumber,
+        ) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if self.is_account_allowed(caller) == false {
+                return Err(Error::NotAllowed)
+            };
+            self.ensure_combatants(caller, attacker, defender)?;
+            if attacker == defender {
+                return Err(Error::NotAllowed)
+            };
+            if expiry == 0 {
+                return Err(Error::InvalidExpiry)
+            };
+            self.check_league(attacker, defender)?;
+            if self.challenges.contains_key(&(attacker, defender)) {
+                return Err(Error::ChallengeExists)
+            };
+            let ranked = self.config.ranked_duels;
+            let window = if ranked { self.config.forfeit_blocks } else { expiry };
+            let expires_at = self.env().block_number().saturating_add(window);
+            let stake = self.env().transferred_balance();
+            self.touch(attacker);
+            self.time_constrain(caller, self.cooldown_of(attacker, self.config.attack_cooldown));
+            self.challenges.insert((attacker, defender), Challenge {
+                expires_at,
+                ranked,
+                stake,
+                staker: caller,
+            });
+            self.env().emit_event(ChallengeIssued {
+                challenger: attacker,
+                challenged: defender,
+                expires_at,
+                ranked,
+                stake,
+            });
+            Ok(())
+        }
+
+        ///Fights a pending duel. Only the owner of the challenged token can accept, sending
+        ///exactly the challenger's stake. The winner's owner takes the pot minus the house fee.
+        ///Accepting costs the caller the same cooldown as an attack
+        #[ink(message, payable)]
+        pub fn accept_challenge(&mut self, attacker: TokenId, defender: TokenId) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if self.is_account_allowed(caller) == false {
+                return Err(Error::NotAllowed)
+            };
+            self.ensure_combatants(caller, defender, attacker)?;
+            let challenge = self.pending_challenge(attacker, defender)?;
+            if self.env().block_number() >= challenge.expires_at {
+                return Err(Error::ChallengeExpired)
+            };
+            if self.env().transferred_balance() != challenge.stake {
+                return Err(Error::WrongStake)
+            };
+            self.ensure_can_strike(attacker, defender)?;
+            //The outcome is settled before anything is written, so a failed payment
+            //leaves the challenge pending
+            let winner = if self.beats(attacker, defender) {
+                attacker
+            } else {
+                defender
+            };
+            self.settle_wager(winner, challenge.stake * 2)?;
+            self.challenges.take(&(attacker, defender));
+            self.touch(defender);
+            self.time_constrain(caller, self.cooldown_of(defender, self.config.attack_cooldown));
+            self.env().emit_event(ChallengeAccepted {
+                challenger: attacker,
+                challenged: defender,
+            });
+            self.fight(BattleKind::Duel, attacker, defender);
+            Ok(())
+        }
+
+        ///Turns a pending duel down. Only the owner of the challenged token can decline
+        #[ink(message)]
+        pub fn decline_challenge(&mut self, attacker: TokenId, defender: TokenId) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if self.owner_of(defender) != Some(caller) {
+                return Err(Error::NotOwner)
+            };
+            let challenge = self.pending_challenge(attacker, defender)?;
+            self.pay(challenge.staker, challenge.stake)?;
+            self.challenges.take(&(attacker, defender));
+            self.env().emit_event(ChallengeDeclined {
+                challenger: attacker,
+                challenged: defender,
+            });
+            Ok(())
+        }
+
+        ///Clears a challenge that was left unanswered past its expiry and refunds the stake.
+        ///Anyone can call this; a ranked challenge is recorded as a forfeit by the challenged token
+        #[ink(message)]
+        pub fn expire_challenge(&mut self, attacker: TokenId, defender: TokenId) -> Result<(), Error> {
+            let challenge = self.pending_challenge(attacker, defender)?;
+            if self.env().block_number() < challenge.expires_at {
+                return Err(Error::NotAllowed)
+            };
+            self.pay(challenge.staker, challenge.stake)?;
+            self.challenges.take(&(attacker, defender));
+            let forfeited = challenge.ranked && self.exists(attacker) && self.exists(defender);
+            if forfeited {
+                self.add_loss(&defender);
+                self.add_victory(&attacker);
+                self.conclude(BattleKind::Duel, attacker, defender, true, 0, false);
+            };
+            self.env().emit_event(ChallengeExpired {
+                challenger: attacker,
+                challenged: defender,
+                forfeited,
+            });
+            Ok(())
+        }
+
+        /// Returns the account collecting the house fees.
+        #[ink(message)]
+        pub fn treasury(&self) -> AccountId {
+            self.treasury
+        }
+
+        /// Changes the account collecting the house fees. Only the admin can do this.
+        #[ink(message)]
+        pub fn set_treasury(&mut self, treasury: AccountId) -> Result<(), Error> {
+            if self.env().caller() != self.admin {
+                return Err(Error::NotAllowed)
+            };
+            self.treasury = treasury;
             Ok(())
         }
 
+        ///Returns the pending challenge from `attacker` to `defender`, if any
+        #[ink(message)]
+        pub fn challenge_of(&self, attacker: TokenId, defender: TokenId) -> Option<Challenge> {
+            self.challenges.get(&(attacker, defender)).cloned()
+        }
+
         #[ink(message)]
         pub fn is_allied(&self, angel: TokenId, _angel: TokenId) -> bool {
             self.allied(angel, _angel)
@@ -418,21 +1755,14 @@ mod erc721 {
             Ok(())
         }
 
-        /// Creates a new token.
+        /// Creates a new token with freshly rolled attributes.
         #[ink(message, payable)]
         pub fn mint(&mut self) -> Result<(), Error> {
             let caller = self.env().caller();
-            if self.balance_of(caller) > 0 {
-                return Err(Error::NotAllowed);
-            }
             let id = self.token_owner.len();
-            self.add_token_to(&caller, id)?;
-            self.env().emit_event(Transfer {
-                from: Some(AccountId::from([0x0; 32])),
-                to: Some(caller),
-                id,
-            });
-            Ok(())
+            let block = self.env().block_number();
+            let seed = self.env().hash_encoded::<Blake2x256, _>(&(caller, id, block));
+            self.mint_with_seed(seed)
         }
 
         /// Deletes an existing token. Only the owner can burn the token.
@@ -454,6 +1784,34 @@ mod erc721 {
             };
             decrease_counter_of(owned_tokens_count, &caller)?;
             occupied.remove_entry();
+            self.attributes.take(&id);
+            self.affinities.take(&id);
+            self.health.take(&id);
+            self.ratings.take(&id);
+            let battles = self.history_count.take(&id).unwrap_or(0);
+            for slot in 0..core::cmp::min(battles, self.config.history_size) {
+                self.history.take(&(id, slot));
+            }
+            self.update_leaderboard(id);
+            self.remove_from_guild(id, false).ok();
+            self.guild_invites.take(&id);
+            for ally in self.allies_of(id) {
+                self.ally(id, ally, false).ok();
+            }
+            self.allies.take(&id);
+            self.effects.take(&id);
+            self.fallen.take(&id);
+            self.forgiven.take(&id);
+            self.set_rank(id, 0);
+            self.activity.take(&id);
+            self.settled_seasons.take(&id);
+            self.protection.take(&id);
+            self.dequeue(id);
+            //War points not claimed yet are forfeited rather than left to the id's next token
+            self.war_contributions.take(&(self.season, id));
+            if let Some(previous) = self.season.checked_sub(1) {
+                self.war_contributions.take(&(previous, id));
+            };
             self.env().emit_event(Transfer {
                 from: Some(caller),
                 to: Some(AccountId::from([0x0; 32])),
@@ -464,6 +1822,29 @@ mod erc721 {
         
 
         //Private functions
+        /// Mints the next token to the caller, rolling its attributes from `seed`.
+        /// Tests call this directly to get predictable attributes.
+        fn mint_with_seed(&mut self, seed: [u8; 32]) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if self.balance_of(caller) > 0 {
+                return Err(Error::NotAllowed);
+            }
+            let id = self.token_owner.len();
+            self.add_token_to(&caller, id)?;
+            self.attributes.insert(id, Attributes::from_seed(&seed));
+            self.affinities.insert(id, Affinity::from_seed(&seed));
+            let block = self.env().block_number();
+            self.activity.insert(id, (block, 0));
+            self.settled_seasons.insert(id, self.season);
+            self.protection.insert(id, block + self.config.protection_blocks);
+            self.env().emit_event(Transfer {
+                from: Some(AccountId::from([0x0; 32])),
+                to: Some(caller),
+                id,
+            });
+            Ok(())
+        }
+
         /// Transfers token `id` `from` the sender to the `to` AccountId.
         fn transfer_token_from(
             &mut self,
@@ -595,22 +1976,216 @@ mod erc721 {
             }
         }
 
-        ///Allows Dominions to form alliances by adding a tuple of
-        ///angels -> bool
+        ///Forms (`approval` true) or dissolves an alliance between two distinct tokens.
+        ///Forming needs both tokens to hold Dominion rank or above, and forming an
+        ///existing alliance again changes nothing. Dissolving needs an existing alliance
         fn ally(&mut self, angel: TokenId, an_ally: TokenId, approval: bool) -> Result<(), Error> {
+            if angel == an_ally {
+                return Err(Error::NotAllowed)
+            };
+            let allied = self.allied(angel, an_ally);
+            if approval {
+                if self.rank(angel) < DOMINION || self.rank(an_ally) < DOMINION {
+                    return Err(Error::NotAllowed)
+                };
+                if !allied {
+                    self.alliances.insert(alliance_key(angel, an_ally), true);
+                    self.allies.entry(angel).or_insert_with(Vec::new).push(an_ally);
+                    self.allies.entry(an_ally).or_insert_with(Vec::new).push(angel);
+                };
+                self.env().emit_event(Alliance {
+                    angel,
+                    ally: an_ally,
+                });
+            } else {
+                if !allied {
+                    return Err(Error::CannotRemove)
+                };
+                self.alliances.take(&alliance_key(angel, an_ally));
+                self.forget_ally(angel, an_ally);
+                self.forget_ally(an_ally, angel);
+                self.env().emit_event(AllianceDissolved {
+                    angel,
+                    ally: an_ally,
+                });
+            }
+            Ok(())
+        }
+
+        fn lead_raid(
+            &mut self,
+            kind: BattleKind,
+            attackers: Vec<TokenId>,
+            victim: TokenId,
+        ) -> Result<(), Error> {
             let caller = self.env().caller();
-            assert!(self.is_account_allowed(caller) == true, "Must wait a few blocks more");
-            if self.is_dominion(angel) == false || self.is_dominion(an_ally) {
+            let leader = *attackers.first().ok_or(Error::NotAllowed)?;
+            self.ensure_combatants(caller, leader, victim)?;
+            let party = attackers.len() as u32;
+            if party < 2 || party > self.config.max_raid_party {
                 return Err(Error::NotAllowed)
             };
-            self.env().emit_event(Alliance {
-                angel,
-                ally: an_ally,
+            for (position, raider) in attackers.iter().enumerate() {
+                let owner = self.owner_of(*raider).ok_or(Error::TokenNotFound)?;
+                if self.is_account_allowed(owner) == false {
+                    return Err(Error::NotAllowed)
+                };
+                if *raider == victim
+                    || attackers[position + 1..]
+                        .iter()
+                        .any(|other| !self.comrades(*raider, *other))
+                {
+                    return Err(Error::NotAllowed)
+                };
+            }
+            for raider in attackers.iter() {
+                self.ensure_can_strike(*raider, victim)?;
+            }
+            if self.stacks_of(victim, StatusEffect::Warded) > 0 {
+                return Err(Error::Warded)
+            };
+            let mut penalties = Vec::new();
+            for raider in attackers.iter() {
+                penalties.push(self.punish_betrayal(*raider, victim));
+                self.engage(*raider, victim);
+            }
+            let mut strength: i32 = 0;
+            let mut ratings: u32 = 0;
+            for raider in attackers.iter() {
+                strength += self.attack_score(*raider) as i32
+                    + i32::from(self.advantage(*raider, victim))
+                    + (RAID_RANK_BONUS * u32::from(self.rank(*raider))) as i32;
+                ratings += self.rating(*raider);
+            }
+            let defense = (self.defense_score(victim)
+                + RAID_RANK_BONUS * u32::from(self.rank(victim)))
+                * (party + 1)
+                / 2;
+            let success = strength >= defense as i32;
+            let party_rating = ratings / party;
+            //Bullies are charged before anything is shared, and a bullying win counts
+            //for nothing
+            let mut bullies = Vec::new();
+            for raider in attackers.iter() {
+                let bullying = self.punish_bullying(*raider, victim);
+                bullies.push(success && bullying);
+            }
+            let victim_rating = self.rating(victim);
+            let rating_share = if success {
+                let expected = expected_score(party_rating, victim_rating);
+                let stake = self.config.rating_k * (1000 - expected) / 1000;
+                let share = core::cmp::min(stake, victim_rating) / party;
+                let mut given = 0;
+                for (raider, bullying) in attackers.iter().zip(bullies.iter()) {
+                    if *bullying {
+                        continue
+                    };
+                    let rating = self.rating(*raider);
+                    self.ratings.insert(*raider, rating + share);
+                    given += share;
+                }
+                self.ratings.insert(victim, victim_rating - given);
+                share
+            } else {
+                let expected = expected_score(victim_rating, party_rating);
+                let share = self.config.rating_k * (1000 - expected) / 1000 / party;
+                let mut taken = 0;
+                for raider in attackers.iter() {
+                    let rating = self.rating(*raider);
+                    let loss = core::cmp::min(share, rating);
+                    self.ratings.insert(*raider, rating - loss);
+                    taken += loss;
+                }
+                self.ratings.insert(victim, victim_rating + taken);
+                share
+            };
+            let block = self.env().block_number();
+            let mut cooldowns: Vec<(AccountId, u32)> = Vec::new();
+            for ((raider, penalty), bullying) in attackers.iter().zip(penalties).zip(bullies) {
+                if !bullying {
+                    if success {
+                        self.add_loss(&victim);
+                        self.add_victory(raider);
+                        let blessing = self.config.bless_blocks;
+                        self.apply_effect(*raider, StatusEffect::Blessed, blessing);
+                        self.score_war(*raider, victim);
+                    } else {
+                        self.add_loss(raider);
+                        self.score_war(victim, *raider);
+                    }
+                    self.record_battle(*raider, BattleRecord {
+                        opponent: victim,
+                        kind,
+                        won: success,
+                        block,
+                    });
+                    self.record_battle(victim, BattleRecord {
+                        opponent: *raider,
+                        kind,
+                        won: !success,
+                        block,
+                    });
+                };
+                //An owner with several raiders waits out the longest cooldown among them
+                if let Some(owner) = self.owner_of(*raider) {
+                    let cooldown = self.cooldown_of(*raider, self.config.raid_cooldown) + penalty;
+                    match cooldowns.iter_mut().find(|(account, _)| *account == owner) {
+                        Some(entry) => entry.1 = core::cmp::max(entry.1, cooldown),
+                        None => cooldowns.push((owner, cooldown)),
+                    }
+                };
+            }
+            for (owner, cooldown) in cooldowns {
+                self.time_constrain(owner, cooldown);
+            }
+            if !success {
+                self.add_victory(&victim);
+            };
+            self.env().emit_event(Raid {
+                victim,
+                participants: attackers,
+                kind,
+                success,
+                rating_share,
+            });
+            Ok(())
+        }
+
+        ///Tokens that may fight side by side: allied or in the same guild
+        fn comrades(&self, token: TokenId, other: TokenId) -> bool {
+            token != other && (self.allied(token, other) || self.same_guild(token, other))
+        }
+
+        ///Dissolves the alliance if `betrayer` is attacking its ally and charges it the
+        ///betrayal losses and rating. Returns the blocks to add to its cooldown
+        fn punish_betrayal(&mut self, betrayer: TokenId, betrayed: TokenId) -> u32 {
+            if !self.allied(betrayer, betrayed) {
+                return 0
+            };
+            self.ally(betrayer, betrayed, false).ok();
+            let losses = self.config.betrayal_losses;
+            for _ in 0..losses {
+                self.add_loss(&betrayer);
+            }
+            let rating = self.rating(betrayer);
+            let rating_penalty = core::cmp::min(self.config.betrayal_rating_penalty, rating);
+            self.ratings.insert(betrayer, rating - rating_penalty);
+            self.update_leaderboard(betrayer);
+            let cooldown = self.config.betrayal_cooldown;
+            self.env().emit_event(Betrayal {
+                betrayer,
+                betrayed,
+                losses,
+                rating_penalty,
+                cooldown,
             });
-            match self.alliances.insert((angel, an_ally), approval) {
-                Some(_) => Err(Error::CannotInsert),
-                None => Ok(()),
-            }   
+            cooldown
+        }
+
+        fn forget_ally(&mut self, token: TokenId, ally: TokenId) {
+            if let Some(allies) = self.allies.get_mut(&token) {
+                allies.retain(|other| *other != ally);
+            };
         }
 
 
@@ -638,6 +2213,69 @@ mod erc721 {
         fn seraphim(&self, token: TokenId) -> bool {
             *self.seraphim.get(&token).unwrap_or(&false)
         }
+        ///Puts the token on tier `rank` of the hierarchy, granting every tier below
+        ///it and revoking every tier above
+        fn set_rank(&mut self, token: TokenId, rank: u8) {
+            let previous = self.rank(token);
+            //A demoted token keeps dropping past limited ranks with no free seat
+            let mut rank = rank;
+            while rank < previous && self.seats_full(rank) {
+                rank -= 1;
+            }
+            for tier in ARCHANGEL..=SERAPHIM {
+                let statuses = self.statuses(tier);
+                if tier <= rank {
+                    statuses.insert(token, true);
+                } else {
+                    statuses.take(&token);
+                }
+            }
+            self.reseat(token, previous);
+            //Powers and above are Warded until they drop below Power
+            let warded = self.stacks_of(token, StatusEffect::Warded) > 0;
+            if rank >= POWER && !warded {
+                self.apply_effect(token, StatusEffect::Warded, BlockNumber::MAX);
+            } else if rank < POWER && warded {
+                self.lift_effect(token, StatusEffect::Warded);
+            };
+        }
+        ///Tokens granted a tier of the hierarchy
+        fn statuses(&mut self, tier: u8) -> &mut StorageHashMap<TokenId, bool> {
+            match tier {
+                ARCHANGEL => &mut self.archangel,
+                PRINCIPALITY => &mut self.principality,
+                POWER => &mut self.power,
+                VIRTUE => &mut self.virtue,
+                DOMINION => &mut self.dominion,
+                THRONE => &mut self.throne,
+                CHERUBIM => &mut self.cherubim,
+                _ => &mut self.seraphim,
+            }
+        }
+        fn rank(&self, token: TokenId) -> u8 {
+            if self.seraphim(token) {
+                SERAPHIM
+            } else if self.cherubim(token) {
+                CHERUBIM
+            } else if self.throne(token) {
+                THRONE
+            } else if self.dominion(token) {
+                DOMINION
+            } else if self.virtue(token) {
+                VIRTUE
+            } else if self.power(token) {
+                POWER
+            } else if self.principality(token) {
+                PRINCIPALITY
+            } else if self.archangel(token) {
+                ARCHANGEL
+            } else {
+                0
+            }
+        }
+        fn rating(&self, token: TokenId) -> u32 {
+            *self.ratings.get(&token).unwrap_or(&self.config.base_rating)
+        }
         fn ready(&self, account: AccountId) -> BlockNumber {
             *self.ready_time.get(&account).unwrap_or(&0)
         }
@@ -649,427 +2287,3394 @@ mod erc721 {
 
         //Returns the victories from an account
         fn victories_of_or_zero(&self, of: &TokenId) -> u64 {
+            if !self.settled(*of) {
+                return 0
+            };
             (*self.victories.get(of).unwrap_or(&0)).into()
         }
 
         ///Returns the losses from an account
         fn losses_of_or_zero(&self, of: &TokenId) -> u64 {
+            if !self.settled(*of) {
+                return 0
+            };
             (*self.losses.get(of).unwrap_or(&0)).into()
         }
 
-        fn allied(&self, angel: TokenId, _angel: TokenId) -> bool {
-            *self.alliances.get(&(angel, _angel)).unwrap_or(&false)
+        ///Attacking strength of a token, derived from its attributes
+        fn attack_score(&self, id: TokenId) -> u32 {
+            let attributes = self.attributes.get(&id).cloned().unwrap_or_default();
+            let blessing = EFFECT_MODIFIER * u32::from(self.stacks_of(id, StatusEffect::Blessed));
+            u32::from(attributes.might) * 2 + u32::from(attributes.grace) + blessing
         }
 
-        /// Gets an operator on other Account's behalf.
-        fn approved_for_all(&self, owner: AccountId, operator: AccountId) -> bool {
-            *self
-                .operator_approvals
-                .get(&(owner, operator))
-                .unwrap_or(&false)
+        ///Defending strength of a token, derived from its attributes
+        fn defense_score(&self, id: TokenId) -> u32 {
+            let attributes = self.attributes.get(&id).cloned().unwrap_or_default();
+            let wounds = EFFECT_MODIFIER * u32::from(self.stacks_of(id, StatusEffect::Wounded));
+            (u32::from(attributes.resilience) * 2 + u32::from(attributes.grace))
+                .saturating_sub(wounds)
         }
 
-        /// Returns true if the AccountId `from` is the owner of token `id`
-        /// or it has been approved on behalf of the token `id` owner.
-        fn approved_or_owner(&self, from: Option<AccountId>, id: TokenId) -> bool {
-            let owner = self.owner_of(id);
-            from != Some(AccountId::from([0x0; 32]))
-                && (from == owner
-                    || from == self.token_approvals.get(&id).cloned()
-                    || self.approved_for_all(
-                        owner.expect("Error with AccountId"),
-                        from.expect("Error with AccountId"),
-                    ))
+        ///Stacks of an effect currently on the token, zero once it has expired
+        fn stacks_of(&self, id: TokenId, effect: StatusEffect) -> u8 {
+            let block = self.env().block_number();
+            self.effects
+                .get(&id)
+                .and_then(|effects| {
+                    effects
+                        .iter()
+                        .find(|active| active.effect == effect && active.expires_at > block)
+                })
+                .map(|active| active.stacks)
+                .unwrap_or(0)
         }
 
-        /// Returns true if token `id` exists or false if it does not.
-        fn exists(&self, id: TokenId) -> bool {
-            self.token_owner.get(&id).is_some() && self.token_owner.contains_key(&id)
+        ///Puts an effect on the token for `duration` blocks. An effect already on the
+        ///token gains a stack, up to its limit, and keeps the later of the two expiries.
+        ///Returns the expiry the effect ends up with
+        fn apply_effect(&mut self, id: TokenId, effect: StatusEffect, duration: u32) -> BlockNumber {
+            self.prune_effects(id);
+            let expires_at = self.env().block_number().saturating_add(duration);
+            let effects = self.effects.entry(id).or_insert_with(Vec::new);
+            let active = match effects.iter_mut().find(|active| active.effect == effect) {
+                Some(active) => {
+                    active.stacks = core::cmp::min(active.stacks + 1, effect.max_stacks());
+                    active.expires_at = core::cmp::max(active.expires_at, expires_at);
+                    *active
+                }
+                None => {
+                    let active = ActiveEffect {
+                        effect,
+                        expires_at,
+                        stacks: 1,
+                    };
+                    effects.push(active);
+                    active
+                }
+            };
+            self.env().emit_event(EffectApplied {
+                token: id,
+                effect,
+                expires_at: active.expires_at,
+                stacks: active.stacks,
+            });
+            active.expires_at
         }
-        
-        ///Will be inherited inside another function that executes this stat change
-        ///along with add_victory
-        fn add_loss(&mut self, victim: &TokenId) -> bool {
-            let Self {
-                losses,
-                ..
-            } = self;
-            let entry = losses.entry(*victim);
-            increase_counter_of_tokenid(entry);
-            true
+
+        ///Takes an effect off the token before it expires
+        fn lift_effect(&mut self, id: TokenId, effect: StatusEffect) {
+            if let Some(effects) = self.effects.get_mut(&id) {
+                effects.retain(|active| active.effect != effect);
+            }
+            self.env().emit_event(EffectExpired { token: id, effect });
         }
 
-        fn add_victory(&mut self, victor: &TokenId) -> bool {
-            let Self {
-                victories,
-                ..
-            } = self;
-            let entry = victories.entry(*victor);
-            increase_counter_of_tokenid(entry);
-            true
+        ///Clears the token's expired effects, emitting `EffectExpired` for each
+        fn prune_effects(&mut self, id: TokenId) {
+            let block = self.env().block_number();
+            let effects = match self.effects.get_mut(&id) {
+                Some(effects) => effects,
+                None => return,
+            };
+            let (active, expired): (Vec<ActiveEffect>, Vec<ActiveEffect>) = effects
+                .iter()
+                .partition(|active| active.expires_at > block);
+            *effects = active;
+            for gone in expired {
+                self.env().emit_event(EffectExpired {
+                    token: id,
+                    effect: gone.effect,
+                });
+            }
         }
 
-        ///Adds the current block number plus a certin number of blocks to the ready_time map
-        fn time_constrain(&mut self, account: AccountId, natural: u32) {
-            let blocked_natural: BlockNumber = natural.into();
-            let limit: BlockNumber = blocked_natural + self.env().block_number();
-            self.ready_time.entry(account).or_insert(limit);
-            self.env().emit_event(Attack {
-                attacker: self.env().caller(),
-                victim: account,
-                block: limit,
+        ///A stunned token cannot attack and a shielded token cannot be attacked
+        fn ensure_can_strike(&mut self, attacker: TokenId, target: TokenId) -> Result<(), Error> {
+            self.settle_season(attacker);
+            self.settle_season(target);
+            self.prune_effects(attacker);
+            self.prune_effects(target);
+            self.check_strike(attacker, target)
+        }
+
+        ///Checks `ensure_can_strike` makes, without clearing expired effects
+        fn check_strike(&self, attacker: TokenId, target: TokenId) -> Result<(), Error> {
+            if self.stacks_of(attacker, StatusEffect::Stunned) > 0 {
+                return Err(Error::Stunned)
+            };
+            if self.stacks_of(target, StatusEffect::Shielded) > 0 {
+                return Err(Error::Shielded)
+            };
+            if self.protected_until(target).is_some() {
+                return Err(Error::Protected)
+            };
+            self.check_league(attacker, target)
+        }
+
+        ///Affinity modifier the attacker gets against the defender, zero if either has none
+        fn advantage(&self, attacker: TokenId, defender: TokenId) -> i8 {
+            match (self.affinities.get(&attacker), self.affinities.get(&defender)) {
+                (Some(attacker), Some(defender)) => self.advantage_of(*attacker, *defender),
+                _ => 0,
+            }
+        }
+
+        ///Resolves a fight from the tokens' attributes and affinities, records the
+        ///victory and the loss, and returns whether the attacker won
+        fn fight(&mut self, kind: BattleKind, attacker: TokenId, defender: TokenId) -> bool {
+            self.engage(attacker, defender);
+            let advantage = self.advantage(attacker, defender);
+            let attacker_won = self.beats(attacker, defender);
+            let bullying =
+                kind != BattleKind::Tournament && self.punish_bullying(attacker, defender);
+            if attacker_won {
+                if !bullying {
+                    self.add_loss(&defender);
+                    self.add_victory(&attacker);
+                };
+            } else {
+                self.add_loss(&attacker);
+                self.add_victory(&defender);
+            }
+            self.conclude(kind, attacker, defender, attacker_won, advantage, bullying);
+            attacker_won
+        }
+
+        ///Whether the attacker's score, with its affinity advantage, reaches the defender's
+        fn beats(&self, attacker: TokenId, defender: TokenId) -> bool {
+            let advantage = self.advantage(attacker, defender);
+            let attack = self.attack_score(attacker) as i32 + i32::from(advantage);
+            attack >= self.defense_score(defender) as i32
+        }
+
+        ///Rates a resolved battle, records it in both tokens' histories and emits
+        ///`Battle`. Victories and losses are counted by the caller. A bullying win is
+        ///neither rated, recorded nor scored in the faction war
+        fn conclude(
+            &mut self,
+            kind: BattleKind,
+            attacker: TokenId,
+            defender: TokenId,
+            attacker_won: bool,
+            advantage: i8,
+            bullying: bool,
+        ) {
+            if attacker_won && bullying {
+                self.env().emit_event(Battle {
+                    attacker,
+                    defender,
+                    kind,
+                    attacker_won,
+                    advantage,
+                    attacker_rating_delta: 0,
+                    defender_rating_delta: 0,
+                });
+                return
+            };
+            let (attacker_rating_delta, defender_rating_delta) = if attacker_won {
+                let (gain, loss) = self.rate(attacker, defender);
+                (gain, -loss)
+            } else {
+                let (gain, loss) = self.rate(defender, attacker);
+                (-loss, gain)
+            };
+            if attacker_won {
+                self.score_war(attacker, defender);
+            } else {
+                self.score_war(defender, attacker);
+            }
+            let block = self.env().block_number();
+            self.record_battle(attacker, BattleRecord {
+                opponent: defender,
+                kind,
+                won: attacker_won,
+                block,
+            });
+            self.record_battle(defender, BattleRecord {
+                opponent: attacker,
+                kind,
+                won: !attacker_won,
+                block,
+            });
+            self.env().emit_event(Battle {
+                attacker,
+                defender,
+                kind,
+                attacker_won,
+                advantage,
+                attacker_rating_delta,
+                defender_rating_delta,
+            });
+        }
+
+        ///Writes a battle into the token's ring buffer, overwriting the oldest one once full
+        fn record_battle(&mut self, id: TokenId, record: BattleRecord) {
+            if self.config.history_size == 0 {
+                return
+            };
+            let count = *self.history_count.get(&id).unwrap_or(&0);
+            self.history.insert((id, count % self.config.history_size), record);
+            self.history_count.insert(id, count + 1);
+        }
+
+        ///Heavy hit: deals damage from the attack score minus the victim's resilience and
+        ///records the loss once the victim's health is exhausted, which restores it
+        fn strike(&mut self, attacker: TokenId, victim: TokenId) {
+            self.engage(attacker, victim);
+            let advantage = self.advantage(attacker, victim);
+            let resilience = self.attributes.get(&victim).map(|a| a.resilience).unwrap_or(0);
+            let attack = self.attack_score(attacker) as i32 + i32::from(advantage);
+            let damage = core::cmp::max(attack - i32::from(resilience), 1) as u32;
+            let health = self.current_health(victim);
+            if damage < health {
+                let block = self.env().block_number();
+                self.health.insert(victim, (health - damage, block));
+                self.apply_effect(victim, StatusEffect::Wounded, self.config.wound_blocks);
+                self.env().emit_event(Strike {
+                    attacker,
+                    victim,
+                    damage,
+                    health: health - damage,
+                });
+                return
+            };
+            self.health.take(&victim);
+            let bullying = self.punish_bullying(attacker, victim);
+            if !bullying {
+                self.add_loss(&victim);
+                self.add_victory(&attacker);
+            };
+            self.conclude(BattleKind::ImprovedAttack, attacker, victim, true, advantage, bullying);
+        }
+
+        ///Moves rating points from the loser to the winner and returns how many
+        ///the winner gained and the loser lost
+        fn rate(&mut self, winner: TokenId, loser: TokenId) -> (i32, i32) {
+            let winner_rating = self.rating(winner);
+            let loser_rating = self.rating(loser);
+            let expected = expected_score(winner_rating, loser_rating);
+            let gain = self.config.rating_k * (1000 - expected) / 1000;
+            let loss = core::cmp::min(gain, loser_rating);
+            self.ratings.insert(winner, winner_rating + gain);
+            self.ratings.insert(loser, loser_rating - loss);
+            self.update_leaderboard(winner);
+            self.update_leaderboard(loser);
+            (gain as i32, loss as i32)
+        }
+
+        ///Full health of a token, grown by its resilience
+        fn max_health(&self, id: TokenId) -> u32 {
+            let resilience = self.attributes.get(&id).map(|a| a.resilience).unwrap_or(0);
+            self.config.base_health + HEALTH_PER_RESILIENCE * u32::from(resilience)
+        }
+
+        ///Health left after the last hit plus what has regenerated since, capped at full health
+        fn current_health(&self, id: TokenId) -> u32 {
+            let max = self.max_health(id);
+            let (health, at) = match self.health.get(&id) {
+                Some(entry) => *entry,
+                None => return max,
+            };
+            if self.config.blocks_per_health == 0 {
+                return max
+            };
+            let regenerated = (self.env().block_number() - at) / self.config.blocks_per_health;
+            core::cmp::min(health.saturating_add(regenerated), max)
+        }
+
+        ///The caller must own `attacker` and the target must exist
+        fn ensure_combatants(
+            &self,
+            caller: AccountId,
+            attacker: TokenId,
+            target: TokenId,
+        ) -> Result<(), Error> {
+            if self.owner_of(attacker) != Some(caller) {
+                return Err(Error::NotOwner)
+            };
+            if !self.exists(target) {
+                return Err(Error::TokenNotFound)
+            };
+            Ok(())
+        }
+
+        ///Pays the pot of a duel to the winner's owner and the house fee to the treasury
+        fn settle_wager(&mut self, winner: TokenId, pot: Balance) -> Result<(), Error> {
+            let fee = pot * Balance::from(self.config.house_fee_bps) / 10_000;
+            let owner = self.owner_of(winner).ok_or(Error::TokenNotFound)?;
+            self.pay(owner, pot - fee)?;
+            self.pay(self.treasury, fee)?;
+            if pot > 0 {
+                self.env().emit_event(WagerSettled {
+                    winner,
+                    prize: pot - fee,
+                    fee,
+                });
+            };
+            Ok(())
+        }
+
+        ///Transfers `amount` out of the contract, skipping empty transfers
+        fn pay(&mut self, to: AccountId, amount: Balance) -> Result<(), Error> {
+            if amount == 0 {
+                return Ok(())
+            };
+            self.env()
+                .transfer(to, amount)
+                .map_err(|_| Error::TransferFailed)
+        }
+
+        fn pending_challenge(&self, attacker: TokenId, defender: TokenId) -> Result<Challenge, Error> {
+            self.challenges
+                .get(&(attacker, defender))
+                .cloned()
+                .ok_or(Error::ChallengeNotFound)
+        }
+
+        ///Shortens a cooldown by 2% for every point of speed the token has
+        fn cooldown_of(&self, id: TokenId, natural: u32) -> u32 {
+            let speed = self.attributes.get(&id).map(|a| a.speed).unwrap_or(0);
+            natural - natural * u32::from(speed) / 50
+        }
+
+        fn allied(&self, angel: TokenId, _angel: TokenId) -> bool {
+            *self.alliances.get(&alliance_key(angel, _angel)).unwrap_or(&false)
+        }
+
+        /// Gets an operator on other Account's behalf.
+        fn approved_for_all(&self, owner: AccountId, operator: AccountId) -> bool {
+            *self
+                .operator_approvals
+                .get(&(owner, operator))
+                .unwrap_or(&false)
+        }
+
+        /// Returns true if the AccountId `from` is the owner of token `id`
+        /// or it has been approved on behalf of the token `id` owner.
+        fn approved_or_owner(&self, from: Option<AccountId>, id: TokenId) -> bool {
+            let owner = self.owner_of(id);
+            from != Some(AccountId::from([0x0; 32]))
+                && (from == owner
+                    || from == self.token_approvals.get(&id).cloned()
+                    || self.approved_for_all(
+                        owner.expect("Error with AccountId"),
+                        from.expect("Error with AccountId"),
+                    ))
+        }
+
+        /// Returns true if token `id` exists or false if it does not.
+        fn exists(&self, id: TokenId) -> bool {
+            self.token_owner.get(&id).is_some() && self.token_owner.contains_key(&id)
+        }
+        
+        ///Will be inherited inside another function that executes this stat change
+        ///along with add_victory
+        fn add_loss(&mut self, victim: &TokenId) -> bool {
+            self.settle_season(*victim);
+            let Self {
+                losses,
+                ..
+            } = self;
+            let entry = losses.entry(*victim);
+            increase_counter_of_tokenid(entry);
+            self.update_leaderboard(*victim);
+            self.fall_if_forsaken(*victim);
+            true
+        }
+
+        fn add_victory(&mut self, victor: &TokenId) -> bool {
+            self.settle_season(*victor);
+            let Self {
+                victories,
+                ..
+            } = self;
+            let entry = victories.entry(*victor);
+            increase_counter_of_tokenid(entry);
+            self.update_leaderboard(*victor);
+            if let Some(progress) = self.fallen.get_mut(victor) {
+                *progress += 1;
+            }
+            true
+        }
+
+        ///Score of a token on the leaderboard
+        fn standing(&self, id: TokenId) -> u32 {
+            match self.config.standing {
+                Standing::Victories => self.victories_of_or_zero(&id) as u32,
+                Standing::Rating => self.rating(id),
+            }
+        }
+
+        ///Moves token `id` to its place on the leaderboard after its score changed,
+        ///or drops it if it no longer exists or no longer makes the cut
+        fn update_leaderboard(&mut self, id: TokenId) {
+            let mut board = core::mem::take(&mut self.leaderboard);
+            board.retain(|entry| *entry != id);
+            let size = self.config.leaderboard_size as usize;
+            if self.exists(id) {
+                let score = self.standing(id);
+                let position = board
+                    .iter()
+                    .position(|other| {
+                        let other_score = self.standing(*other);
+                        score > other_score || (score == other_score && id < *other)
+                    })
+                    .unwrap_or(board.len());
+                if position < size {
+                    board.insert(position, id);
+                }
+            }
+            board.truncate(size);
+            self.leaderboard = board;
+        }
+
+        ///Sorts the listed tokens again, e.g. after the board's rules changed
+        fn reorder_leaderboard(&mut self) {
+            let mut board = core::mem::take(&mut self.leaderboard);
+            board.sort_unstable_by(|a, b| {
+                self.standing(*b)
+                    .cmp(&self.standing(*a))
+                    .then(a.cmp(b))
             });
+            board.truncate(self.config.leaderboard_size as usize);
+            self.leaderboard = board;
+        }
+
+        ///Adds the current block number plus a certin number of blocks to the ready_time map
+        fn time_constrain(&mut self, account: AccountId, natural: u32) {
+            let blocked_natural: BlockNumber = natural.into();
+            let limit: BlockNumber = blocked_natural + self.env().block_number();
+            self.ready_time.insert(account, limit);
         }
         ///Checks the account id's respective block number, in ready_time map,
         ///if a certain number of blocks have passed
         fn is_account_allowed(&self, id: AccountId) -> bool {
             let last_block = self.is_ready(id);
-            return last_block > self.env().block_number()
+            return last_block <= self.env().block_number()
         }
 
     }
 
-    fn decrease_counter_of(
-        hmap: &mut StorageHashMap<AccountId, u32>,
-        of: &AccountId,
-    ) -> Result<(), Error> {
-        let count = (*hmap).get_mut(of).ok_or(Error::CannotFetchValue)?;
-        *count -= 1;
-        Ok(())
-    }
+    ///Tournaments
+    impl Erc721 {
+        /// Opens a single-elimination tournament. Only the admin can do this.
+        #[ink(message)]
+        pub fn create_tournament(
+            &mut self,
+            entry_fee: Balance,
+            max_entrants: u32,
+            registration_deadline: BlockNumber,
+        ) -> Result<TournamentId, Error> {
+            if self.env().caller() != self.admin {
+                return Err(Error::NotAllowed)
+            };
+            if max_entrants < 2 || registration_deadline <= self.env().block_number() {
+                return Err(Error::NotAllowed)
+            };
+            let id = self.next_tournament;
+            self.next_tournament += 1;
+            self.tournaments.insert(id, Tournament {
+                entry_fee,
+                max_entrants,
+                registration_deadline,
+                phase: TournamentPhase::Registration,
+                bracket: Vec::new(),
+                eliminated: Vec::new(),
+                round: 0,
+                prize_pool: 0,
+            });
+            self.env().emit_event(TournamentCreated {
+                tournament: id,
+                entry_fee,
+                max_entrants,
+                registration_deadline,
+            });
+            Ok(id)
+        }
 
-    fn decrease_counter_of_tokenid(
-        hmap: &mut StorageHashMap<TokenId, u32>,
-        token: &TokenId
-    ) -> Result<(), Error> {
-        let count = (*hmap).get_mut(&token).ok_or(Error::CannotFetchValue)?;
-        *count -= 1;
-        Ok(())
-    }
+        /// Enters the caller's token, paying exactly the entry fee.
+        #[ink(message, payable)]
+        pub fn enter_tournament(&mut self, id: TournamentId, token: TokenId) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if self.owner_of(token) != Some(caller) {
+                return Err(Error::NotOwner)
+            };
+            let mut tournament = self.tournament_in(id, TournamentPhase::Registration)?;
+            if self.env().block_number() >= tournament.registration_deadline {
+                return Err(Error::WrongPhase)
+            };
+            if tournament.bracket.len() as u32 >= tournament.max_entrants {
+                return Err(Error::TournamentFull)
+            };
+            if tournament.bracket.contains(&token) {
+                return Err(Error::AlreadyRegistered)
+            };
+            if self.env().transferred_balance() != tournament.entry_fee {
+                return Err(Error::WrongStake)
+            };
+            //Entering gives up a newcomer's protection, as attacking would
+            self.protection.take(&token);
+            self.touch(token);
+            tournament.bracket.push(token);
+            tournament.prize_pool += tournament.entry_fee;
+            self.tournaments.insert(id, tournament);
+            self.env().emit_event(TournamentEntered {
+                tournament: id,
+                token,
+            });
+            Ok(())
+        }
 
-    /// Increase token counter from the `of` AccountId.
-    fn increase_counter_of(entry: Entry<AccountId, u32>) {
-        entry.and_modify(|v| *v += 1).or_insert(1);
-    }
+        /// Closes registration once the deadline has passed and seeds the bracket by
+        /// rating, ties going to the lower token id. Anyone can call this. A tournament
+        /// with fewer than two entrants is cancelled and its fees refunded.
+        #[ink(message)]
+        pub fn seed_tournament(&mut self, id: TournamentId) -> Result<(), Error> {
+            let mut tournament = self.tournament_in(id, TournamentPhase::Registration)?;
+            if self.env().block_number() < tournament.registration_deadline {
+                return Err(Error::WrongPhase)
+            };
+            if tournament.bracket.len() < 2 {
+                for token in tournament.bracket.iter() {
+                    let owner = self.owner_of(*token).unwrap_or(self.treasury);
+                    self.pay(owner, tournament.entry_fee)?;
+                }
+                tournament.phase = TournamentPhase::Cancelled;
+                self.tournaments.insert(id, tournament);
+                self.env().emit_event(TournamentFinished {
+                    tournament: id,
+                    champion: None,
+                    prize_pool: 0,
+                });
+                return Ok(())
+            };
+            tournament.bracket.sort_unstable_by(|a, b| {
+                self.rating(*b).cmp(&self.rating(*a)).then(a.cmp(b))
+            });
+            tournament.phase = TournamentPhase::Running;
+            self.env().emit_event(TournamentSeeded {
+                tournament: id,
+                bracket: tournament.bracket.clone(),
+            });
+            self.tournaments.insert(id, tournament);
+            Ok(())
+        }
 
-    fn increase_counter_of_tokenid(entry: Entry<TokenId, u32>) {
-        entry.and_modify(|v| *v += 1).or_insert(1);
+        /// Fights the next round: the best remaining seed meets the worst, the second
+        /// best the second worst and so on, the middle seed getting a bye when the count
+        /// is odd. Anyone can call this. The last round pays out the prize pool.
+        #[ink(message)]
+        pub fn play_round(&mut self, id: TournamentId) -> Result<(), Error> {
+            let mut tournament = self.tournament_in(id, TournamentPhase::Running)?;
+            let bracket = core::mem::take(&mut tournament.bracket);
+            let len = bracket.len();
+            let pairs: Vec<(TokenId, TokenId)> = (0..len / 2)
+                .map(|high| (bracket[high], bracket[len - 1 - high]))
+                .collect();
+            let mut advancing = Vec::with_capacity((len + 1) / 2);
+            let mut matches = Vec::with_capacity(pairs.len());
+            for (high, low) in pairs {
+                let (winner, loser, fought) = self.tournament_outcome(high, low);
+                advancing.push(winner);
+                tournament.eliminated.push(loser);
+                if fought {
+                    matches.push((high, low));
+                };
+            }
+            if len % 2 == 1 {
+                advancing.push(bracket[len / 2]);
+            };
+            tournament.round += 1;
+            tournament.bracket = advancing.clone();
+            //Outcomes are known before any match is fought, so the prizes are paid
+            //before anything is written
+            if tournament.bracket.len() == 1 {
+                self.finish_tournament(id, &tournament)?;
+                tournament.phase = TournamentPhase::Finished;
+            };
+            for (high, low) in matches {
+                self.fight(BattleKind::Tournament, high, low);
+            }
+            self.env().emit_event(TournamentRound {
+                tournament: id,
+                round: tournament.round,
+                advancing,
+            });
+            self.tournaments.insert(id, tournament);
+            Ok(())
+        }
+
+        /// Returns the tournament, if it exists.
+        #[ink(message)]
+        pub fn tournament(&self, id: TournamentId) -> Option<Tournament> {
+            self.tournaments.get(&id).cloned()
+        }
+
+        /// Returns the final placements, champion first, once the tournament is finished.
+        #[ink(message)]
+        pub fn tournament_placements(&self, id: TournamentId) -> Vec<TokenId> {
+            match self.tournaments.get(&id) {
+                Some(tournament) if tournament.phase == TournamentPhase::Finished => {
+                    placements(tournament)
+                }
+                _ => Vec::new(),
+            }
+        }
+
+        fn tournament_in(
+            &self,
+            id: TournamentId,
+            phase: TournamentPhase,
+        ) -> Result<Tournament, Error> {
+            let tournament = self
+                .tournaments
+                .get(&id)
+                .cloned()
+                .ok_or(Error::TournamentNotFound)?;
+            if tournament.phase != phase {
+                return Err(Error::WrongPhase)
+            };
+            Ok(tournament)
+        }
+
+        ///Returns (winner, loser, fought) of a tournament match without fighting it. A
+        ///token that was burnt in the meantime loses by default, the lower seed first.
+        ///Entrants are bound to their bracket, so no effect or protection spares them a match
+        fn tournament_outcome(&mut self, high: TokenId, low: TokenId) -> (TokenId, TokenId, bool) {
+            if !self.exists(low) {
+                return (high, low, false)
+            };
+            if !self.exists(high) {
+                return (low, high, false)
+            };
+            for token in [high, low].iter() {
+                self.settle_season(*token);
+                self.prune_effects(*token);
+            }
+            if self.beats(high, low) {
+                (high, low, true)
+            } else {
+                (low, high, true)
+            }
+        }
+
+        ///Pays the prize shares to the owners of the placed tokens and adds the rest to the
+        ///war chest
+        fn finish_tournament(&mut self, id: TournamentId, tournament: &Tournament) -> Result<(), Error> {
+            let shares = self.config.prize_shares_bps;
+            let mut paid: Balance = 0;
+            for (token, share) in placements(tournament).iter().zip(shares.iter()) {
+                let prize = tournament.prize_pool * Balance::from(*share) / 10_000;
+                let owner = self.owner_of(*token).unwrap_or(self.treasury);
+                self.pay(owner, prize)?;
+                paid += prize;
+            }
+            self.war_chest += tournament.prize_pool.saturating_sub(paid);
+            self.env().emit_event(TournamentFinished {
+                tournament: id,
+                champion: tournament.bracket.first().cloned(),
+                prize_pool: tournament.prize_pool,
+            });
+            Ok(())
+        }
     }
 
-    /// Unit tests
-    #[cfg(test)]
-    mod tests {
-        /// Imports all the definitions from the outer scope so we can use them here.
-        use super::*;
-        use ink_env::{
-            call,
-            test,
-        };
-        use ink_lang as ink;
+    ///Guilds
+    impl Erc721 {
+        /// Founds a guild led by the caller's token.
+        #[ink(message)]
+        pub fn create_guild(&mut self, leader: TokenId) -> Result<GuildId, Error> {
+            let caller = self.env().caller();
+            if self.owner_of(leader) != Some(caller) {
+                return Err(Error::NotOwner)
+            };
+            if self.guild_memberships.contains_key(&leader) {
+                return Err(Error::AlreadyInGuild)
+            };
+            let id = self.next_guild;
+            self.next_guild += 1;
+            let mut members = Vec::new();
+            members.push(leader);
+            self.touch(leader);
+            self.guilds.insert(id, Guild { leader, members });
+            self.guild_memberships.insert(leader, id);
+            self.env().emit_event(GuildCreated { guild: id, leader });
+            Ok(id)
+        }
+
+        /// Invites `token` into the guild led by the caller's `leader` token.
+        #[ink(message)]
+        pub fn invite_to_guild(&mut self, leader: TokenId, token: TokenId) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if self.owner_of(leader) != Some(caller) {
+                return Err(Error::NotOwner)
+            };
+            if !self.exists(token) {
+                return Err(Error::TokenNotFound)
+            };
+            let guild = self.guild_of(leader).ok_or(Error::NotInGuild)?;
+            if self.guilds.get(&guild).map(|g| g.leader) != Some(leader) {
+                return Err(Error::NotAllowed)
+            };
+            self.touch(leader);
+            let invites = self.guild_invites.entry(token).or_insert_with(Vec::new);
+            if !invites.contains(&guild) {
+                invites.push(guild);
+            };
+            self.env().emit_event(GuildInvited { guild, token });
+            Ok(())
+        }
+
+        /// Returns whether the token has a pending invitation to the guild.
+        #[ink(message)]
+        pub fn is_invited(&self, guild: GuildId, token: TokenId) -> bool {
+            self.guild_invites
+                .get(&token)
+                .map_or(false, |invites| invites.contains(&guild))
+        }
+
+        /// Adds the caller's token to a guild that invited it and still has room.
+        #[ink(message)]
+        pub fn join_guild(&mut self, guild: GuildId, token: TokenId) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if self.owner_of(token) != Some(caller) {
+                return Err(Error::NotOwner)
+            };
+            if self.guild_memberships.contains_key(&token) {
+                return Err(Error::AlreadyInGuild)
+            };
+            if !self.guilds.contains_key(&guild) {
+                return Err(Error::GuildNotFound)
+            };
+            if !self.is_invited(guild, token) {
+                return Err(Error::NotInvited)
+            };
+            let max_size = self.config.max_guild_size as usize;
+            let entry = self.guilds.get_mut(&guild).ok_or(Error::GuildNotFound)?;
+            if entry.members.len() >= max_size {
+                return Err(Error::GuildFull)
+            };
+            entry.members.push(token);
+            if let Some(invites) = self.guild_invites.get_mut(&token) {
+                invites.retain(|invite| *invite != guild);
+            }
+            self.touch(token);
+            self.guild_memberships.insert(token, guild);
+            self.env().emit_event(GuildJoined { guild, token });
+            Ok(())
+        }
+
+        /// Takes the caller's token out of its guild. A leaving leader hands over to the
+        /// longest standing member; the last member to leave disbands the guild.
+        #[ink(message)]
+        pub fn leave_guild(&mut self, token: TokenId) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if self.owner_of(token) != Some(caller) {
+                return Err(Error::NotOwner)
+            };
+            self.remove_from_guild(token, false)?;
+            self.touch(token);
+            Ok(())
+        }
+
+        /// Removes `member` from the guild led by the caller's `leader` token.
+        #[ink(message)]
+        pub fn kick(&mut self, leader: TokenId, member: TokenId) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if self.owner_of(leader) != Some(caller) {
+                return Err(Error::NotOwner)
+            };
+            let guild = self.guild_of(member).ok_or(Error::NotInGuild)?;
+            if leader == member || self.guilds.get(&guild).map(|g| g.leader) != Some(leader) {
+                return Err(Error::NotAllowed)
+            };
+            self.remove_from_guild(member, true)?;
+            self.touch(leader);
+            Ok(())
+        }
+
+        /// Returns the guild the token belongs to, if any.
+        #[ink(message)]
+        pub fn guild_of(&self, token: TokenId) -> Option<GuildId> {
+            self.guild_memberships.get(&token).cloned()
+        }
+
+        /// Returns the members of a guild, the leader first.
+        #[ink(message)]
+        pub fn members_of(&self, guild: GuildId) -> Vec<TokenId> {
+            self.guilds
+                .get(&guild)
+                .map(|g| g.members.clone())
+                .unwrap_or_default()
+        }
+
+        /// Returns the guild, if it exists.
+        #[ink(message)]
+        pub fn guild(&self, guild: GuildId) -> Option<Guild> {
+            self.guilds.get(&guild).cloned()
+        }
+
+        fn same_guild(&self, token: TokenId, other: TokenId) -> bool {
+            match self.guild_of(token) {
+                Some(guild) => self.guild_of(other) == Some(guild),
+                None => false,
+            }
+        }
+
+        fn remove_from_guild(&mut self, token: TokenId, kicked: bool) -> Result<(), Error> {
+            let guild = self.guild_memberships.take(&token).ok_or(Error::NotInGuild)?;
+            let mut entry = self.guilds.take(&guild).ok_or(Error::GuildNotFound)?;
+            entry.members.retain(|member| *member != token);
+            if let Some(successor) = entry.members.first().cloned() {
+                if entry.leader == token {
+                    entry.leader = successor;
+                };
+                self.guilds.insert(guild, entry);
+            };
+            self.env().emit_event(GuildLeft {
+                guild,
+                token,
+                kicked,
+            });
+            Ok(())
+        }
+    }
+
+    ///Seasons
+    impl Erc721 {
+        /// Starts the next season. Each token's victories and losses are archived under
+        /// the ended season, and it drops the `season_rank_decay` tiers in force now, the
+        /// next time it plays or has decay applied. Rank queries count the drop at once.
+        /// Only the admin can do this.
+        #[ink(message)]
+        pub fn end_season(&mut self) -> Result<(), Error> {
+            if self.env().caller() != self.admin {
+                return Err(Error::NotAllowed)
+            };
+            let season = self.season;
+            if self.config.standing == Standing::Victories {
+                self.leaderboard = Vec::new();
+            };
+            self.settle_war(season);
+            let decay = self.decay_by(season) + u32::from(self.config.season_rank_decay);
+            self.season_decay.insert(season + 1, decay);
+            self.season += 1;
+            self.env().emit_event(SeasonEnded { season });
+            Ok(())
+        }
+
+        /// Returns the season currently being played.
+        #[ink(message)]
+        pub fn current_season(&self) -> SeasonId {
+            self.season
+        }
+
+        /// Returns the token's record in a finished season, all zero if it did not play.
+        #[ink(message)]
+        pub fn season_stats(&self, season: SeasonId, id: TokenId) -> SeasonStats {
+            if let Some(stats) = self.season_stats.get(&(season, id)) {
+                return *stats
+            };
+            //A record not archived yet is still in the live counters
+            if season < self.season && self.settled_seasons.get(&id) == Some(&season) {
+                return SeasonStats {
+                    victories: *self.victories.get(&id).unwrap_or(&0),
+                    losses: *self.losses.get(&id).unwrap_or(&0),
+                }
+            };
+            SeasonStats::default()
+        }
+
+        ///Whether the token's live victories and losses belong to the current season
+        fn settled(&self, id: TokenId) -> bool {
+            self.settled_seasons
+                .get(&id)
+                .map_or(true, |season| *season == self.season)
+        }
+
+        ///Archives the token's record of the season it last played in and drops it
+        ///tiers of season decay owed for every season ended since
+        fn settle_season(&mut self, id: TokenId) {
+            let settled = match self.settled_seasons.get(&id) {
+                Some(season) => *season,
+                None => return,
+            };
+            if settled >= self.season {
+                return
+            };
+            let stats = SeasonStats {
+                victories: self.victories.take(&id).unwrap_or(0),
+                losses: self.losses.take(&id).unwrap_or(0),
+            };
+            if stats != SeasonStats::default() {
+                self.season_stats.insert((settled, id), stats);
+            };
+            self.forgiven.take(&id);
+            let decay = self.season_decay_of(id);
+            self.settled_seasons.insert(id, self.season);
+            let rank = self.rank(id);
+            if decay > 0 && rank > 0 {
+                self.set_rank(id, rank.saturating_sub(decay));
+            };
+        }
+
+        ///Tiers of season decay owed by the start of `season`
+        fn decay_by(&self, season: SeasonId) -> u32 {
+            *self.season_decay.get(&season).unwrap_or(&0)
+        }
+
+        ///Tiers of season decay the token owes for the seasons ended since it last played
+        fn season_decay_of(&self, id: TokenId) -> u8 {
+            let settled = match self.settled_seasons.get(&id) {
+                Some(season) => *season,
+                None => return 0,
+            };
+            let owed = self.decay_by(self.season) - self.decay_by(settled);
+            core::cmp::min(owed, u32::from(u8::MAX)) as u8
+        }
+
+        ///The token's rank once the season decay it owes is taken off
+        fn effective_rank(&self, id: TokenId) -> u8 {
+            self.rank(id).saturating_sub(self.season_decay_of(id))
+        }
+    }
+
+    ///Fallen
+    impl Erc721 {
+        /// Returns the side the token fights for.
+        #[ink(message)]
+        pub fn faction_of(&self, id: TokenId) -> Faction {
+            if self.fallen.contains_key(&id) {
+                Faction::Fallen
+            } else {
+                Faction::Heaven
+            }
+        }
+
+        /// Returns how deep in the Fallen hierarchy the token is, from 1 to 3, or 0
+        /// if it has not fallen. Every `fall_margin` losses more than victories take
+        /// it one circle lower.
+        #[ink(message)]
+        pub fn depth_of(&self, id: TokenId) -> u8 {
+            if !self.fallen.contains_key(&id) {
+                return 0
+            };
+            let net_losses = self.losses_count(id).saturating_sub(self.victories_count(id));
+            let depth = net_losses / u64::from(core::cmp::max(self.config.fall_margin, 1));
+            core::cmp::min(core::cmp::max(depth, 1), u64::from(FALLEN_DEPTHS)) as u8
+        }
+
+        /// Returns how many victories the Fallen token has won towards its redemption.
+        #[ink(message)]
+        pub fn quest_progress(&self, id: TokenId) -> Option<u32> {
+            self.fallen.get(&id).cloned()
+        }
+
+        /// Corrupts a heavenly `target` with the caller's Fallen token, wounding it
+        /// once per circle the Fallen token has descended.
+        #[ink(message)]
+        pub fn corrupt(&mut self, from: TokenId, target: TokenId) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if self.is_account_allowed(caller) == false {
+                return Err(Error::NotAllowed)
+            };
+            self.ensure_combatants(caller, from, target)?;
+            if self.faction_of(from) != Faction::Fallen
+                || self.faction_of(target) == Faction::Fallen
+            {
+                return Err(Error::NotAllowed)
+            };
+            self.ensure_can_strike(from, target)?;
+            self.engage(from, target);
+            for _ in 0..self.depth_of(from) {
+                self.apply_effect(target, StatusEffect::Wounded, self.config.wound_blocks);
+            }
+            let cooldown = self.cooldown_of(from, self.config.attack_cooldown);
+            self.time_constrain(caller, cooldown);
+            Ok(())
+        }
+
+        /// Returns the caller's Fallen token to Heaven once it has won
+        /// `redemption_quest` victories since it fell. It comes back without ranks.
+        #[ink(message)]
+        pub fn seek_redemption(&mut self, id: TokenId) -> Result<(), Error> {
+            if self.owner_of(id) != Some(self.env().caller()) {
+                return Err(Error::NotOwner)
+            };
+            let progress = match self.fallen.get(&id) {
+                Some(progress) => *progress,
+                None => return Err(Error::NotAllowed),
+            };
+            if progress < self.config.redemption_quest {
+                return Err(Error::QuestIncomplete)
+            };
+            self.touch(id);
+            self.fallen.take(&id);
+            //The losses it carries out of the Fall do not count towards the next one
+            let deficit = self.losses_count(id).saturating_sub(self.victories_count(id));
+            self.forgiven.insert(id, deficit);
+            self.env().emit_event(Redeemed {
+                token: id,
+                season: self.season,
+            });
+            Ok(())
+        }
+
+        ///Casts the token out of Heaven, stripping its ranks, once its losses exceed
+        ///its victories by more than `fall_margin`
+        fn fall_if_forsaken(&mut self, id: TokenId) {
+            if self.fallen.contains_key(&id) {
+                return
+            };
+            let victories = self.victories_count(id);
+            let losses = self.losses_count(id);
+            let forgiven = self.forgiven.get(&id).copied().unwrap_or(0);
+            if losses <= victories + u64::from(self.config.fall_margin) + forgiven {
+                return
+            };
+            let rank = self.rank(id);
+            self.set_rank(id, 0);
+            self.fallen.insert(id, 0);
+            self.env().emit_event(Fell {
+                token: id,
+                victories: victories as u32,
+                losses: losses as u32,
+                rank,
+            });
+        }
+    }
+
+    ///Seats
+    impl Erc721 {
+        /// Returns the tokens whose highest rank is `rank`. Limited ranks list their
+        /// holders in the order they were seated. Tokens still owing season decay that
+        /// takes them below `rank` neither count nor hold a seat.
+        #[ink(message)]
+        pub fn rank_holders(&self, rank: u8) -> Vec<TokenId> {
+            let holders = match self.seat_limit(rank) {
+                Some(_) => self.seat_holders.get(&rank).cloned().unwrap_or_default(),
+                None => self.token_owner.keys().cloned().collect(),
+            };
+            holders
+                .into_iter()
+                .filter(|id| self.effective_rank(*id) == rank)
+                .collect()
+        }
+
+        /// Challenges the `holder` of a full rank for its seat. The caller's `challenger`
+        /// needs the victories to ascend to that rank. If it wins, it takes the seat
+        /// and the holder drops to the next rank with a free seat.
+        #[ink(message)]
+        pub fn dethrone(&mut self, challenger: TokenId, holder: TokenId) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if self.is_account_allowed(caller) == false {
+                return Err(Error::NotAllowed)
+            };
+            self.ensure_combatants(caller, challenger, holder)?;
+            if self.faction_of(challenger) == Faction::Fallen {
+                return Err(Error::Fallen)
+            };
+            let seat = self.rank(holder);
+            if !self.seats_full(seat)
+                || self.rank(challenger) >= seat
+                || self.victories_count(challenger) < ascension_victories(seat)
+            {
+                return Err(Error::NotAllowed)
+            };
+            self.ensure_can_strike(challenger, holder)?;
+            let penalty = self.punish_betrayal(challenger, holder);
+            if self.fight(BattleKind::Dethrone, challenger, holder) {
+                //The challenger moves up first, freeing the seat it held
+                self.set_rank(challenger, seat);
+                self.set_rank(holder, seat - 1);
+            };
+            let cooldown = self.cooldown_of(challenger, self.config.heavy_cooldown);
+            self.time_constrain(caller, cooldown + penalty);
+            Ok(())
+        }
+
+        ///Seats of a rank, unlimited below Thrones
+        fn seat_limit(&self, rank: u8) -> Option<u32> {
+            if rank < THRONE {
+                return None
+            };
+            self.config.seats.get(usize::from(rank - THRONE)).cloned()
+        }
+
+        fn seats_full(&self, rank: u8) -> bool {
+            match self.seat_limit(rank) {
+                Some(seats) => self.rank_holders(rank).len() as u32 >= seats,
+                None => false,
+            }
+        }
+
+        ///Moves the token between seat lists after its highest rank changed from `previous`
+        fn reseat(&mut self, token: TokenId, previous: u8) {
+            let rank = self.rank(token);
+            if rank == previous {
+                return
+            };
+            if self.seat_limit(previous).is_some() {
+                if let Some(holders) = self.seat_holders.get_mut(&previous) {
+                    holders.retain(|holder| *holder != token);
+                }
+                self.env().emit_event(SeatChanged {
+                    token,
+                    rank: previous,
+                    seated: false,
+                });
+            };
+            if self.seat_limit(rank).is_some() {
+                self.seat_holders
+                    .entry(rank)
+                    .or_insert_with(Vec::new)
+                    .push(token);
+                self.env().emit_event(SeatChanged {
+                    token,
+                    rank,
+                    seated: true,
+                });
+            };
+        }
+    }
+
+    ///Inactivity
+    impl Erc721 {
+        /// Returns the block the token last acted in.
+        #[ink(message)]
+        pub fn last_active(&self, id: TokenId) -> Option<BlockNumber> {
+            self.activity.get(&id).map(|(block, _)| *block)
+        }
+
+        /// Applies the rank decay the idle tokens among `ids` are due. The caller is paid
+        /// `decay_bounty` for every tier taken, as far as the decay pool allows.
+        #[ink(message)]
+        pub fn apply_decay(&mut self, ids: Vec<TokenId>) -> Result<(), Error> {
+            let mut tiers: u32 = 0;
+            for id in ids {
+                tiers += u32::from(self.decay(id));
+            }
+            let bounty = core::cmp::min(
+                self.config.decay_bounty * Balance::from(tiers),
+                self.decay_pool,
+            );
+            self.pay(self.env().caller(), bounty)?;
+            self.decay_pool -= bounty;
+            Ok(())
+        }
+
+        /// Returns the funds left for decay bounties.
+        #[ink(message)]
+        pub fn decay_pool(&self) -> Balance {
+            self.decay_pool
+        }
+
+        /// Adds the transferred value to the decay pool.
+        #[ink(message, payable)]
+        pub fn fund_decay_pool(&mut self) {
+            self.decay_pool += self.env().transferred_balance();
+        }
+
+        /// Returns the block until which the newly minted token cannot be targeted,
+        /// or `None` once its protection is over.
+        #[ink(message)]
+        pub fn protected_until(&self, id: TokenId) -> Option<BlockNumber> {
+            self.protection
+                .get(&id)
+                .cloned()
+                .filter(|until| *until > self.env().block_number())
+        }
+
+        ///Marks the attacker active, ending its newcomer protection, and applies any
+        ///decay the target is due before they fight
+        fn engage(&mut self, attacker: TokenId, target: TokenId) {
+            self.touch(attacker);
+            self.protection.take(&attacker);
+            self.decay(target);
+        }
+
+        ///Applies any decay the token is due, then marks it active in the current block
+        fn touch(&mut self, id: TokenId) {
+            self.decay(id);
+            self.activity.insert(id, (self.env().block_number(), 0));
+        }
+
+        ///Drops the token one tier for every `decay_period` it has been idle past
+        ///`idle_blocks` that it has not been charged for yet. Returns the tiers taken
+        fn decay(&mut self, id: TokenId) -> u8 {
+            self.settle_season(id);
+            let (last_active, charged) = match self.activity.get(&id) {
+                Some(activity) => *activity,
+                None => return 0,
+            };
+            let idle = self.env().block_number().saturating_sub(last_active);
+            if idle < self.config.idle_blocks {
+                return 0
+            };
+            let periods = 1 + (idle - self.config.idle_blocks)
+                / core::cmp::max(self.config.decay_period, 1);
+            if periods <= charged {
+                return 0
+            };
+            self.activity.insert(id, (last_active, periods));
+            let rank = self.rank(id);
+            let tiers = core::cmp::min(periods - charged, u32::from(rank)) as u8;
+            if tiers == 0 {
+                return 0
+            };
+            //Seats that are full may drop the token further than the tiers it decayed
+            self.set_rank(id, rank - tiers);
+            let to = self.rank(id);
+            self.env().emit_event(Decayed {
+                token: id,
+                from: rank,
+                to,
+            });
+            tiers
+        }
+    }
+
+    ///Leagues
+    impl Erc721 {
+        /// Dry run of an attack by `attacker` on `target`: returns the error the attack
+        /// would fail with right now, if any.
+        #[ink(message)]
+        pub fn can_attack(&self, attacker: TokenId, target: TokenId) -> Result<(), Error> {
+            let owner = self.owner_of(attacker).ok_or(Error::TokenNotFound)?;
+            if self.is_account_allowed(owner) == false || attacker == target {
+                return Err(Error::NotAllowed)
+            };
+            if !self.exists(target) {
+                return Err(Error::TokenNotFound)
+            };
+            self.check_strike(attacker, target)
+        }
+
+        ///Refuses attacks across more than `league_distance` tiers in bracketed leagues
+        fn check_league(&self, attacker: TokenId, target: TokenId) -> Result<(), Error> {
+            if self.config.league != League::Bracketed {
+                return Ok(())
+            };
+            let (attacker_rank, target_rank) = (self.rank(attacker), self.rank(target));
+            let distance = core::cmp::max(attacker_rank, target_rank)
+                - core::cmp::min(attacker_rank, target_rank);
+            if distance > self.config.league_distance {
+                return Err(Error::OutOfLeague)
+            };
+            Ok(())
+        }
+
+        ///In punitive leagues, charges `league_penalty` rating to an attacker more than
+        ///`league_distance` tiers above its target. Returns whether it did, in which
+        ///case the attack wins nothing
+        fn punish_bullying(&mut self, attacker: TokenId, target: TokenId) -> bool {
+            if self.config.league != League::Punitive
+                || self.rank(attacker) <= self.rank(target).saturating_add(self.config.league_distance)
+            {
+                return false
+            };
+            let rating = self.rating(attacker);
+            self.ratings
+                .insert(attacker, rating.saturating_sub(self.config.league_penalty));
+            self.update_leaderboard(attacker);
+            true
+        }
+    }
+
+    ///Matchmaking
+    impl Erc721 {
+        /// Puts the caller's token in the matchmaking queue. If a token is already
+        /// waiting in its band, the waiting token attacks it at once and both leave
+        /// the queue. Otherwise the token waits for the next arrival in its band.
+        /// A waiting token that changed hands, whose owner is cooling down, that has
+        /// moved to another band or that can no longer attack the arrival leaves the
+        /// queue unmatched. Stunned and shielded tokens cannot queue.
+        #[ink(message)]
+        pub fn enter_queue(&mut self, token: TokenId) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if self.is_account_allowed(caller) == false {
+                return Err(Error::NotAllowed)
+            };
+            if self.owner_of(token) != Some(caller) {
+                return Err(Error::NotOwner)
+            };
+            if self.queued.contains_key(&token) {
+                return Err(Error::AlreadyQueued)
+            };
+            self.prune_effects(token);
+            if self.stacks_of(token, StatusEffect::Stunned) > 0 {
+                return Err(Error::Stunned)
+            };
+            if self.stacks_of(token, StatusEffect::Shielded) > 0 {
+                return Err(Error::Shielded)
+            };
+            //Queueing gives up a newcomer's protection, as attacking would
+            self.protection.take(&token);
+            self.touch(token);
+            let band = self.band_of(token);
+            match self.opponent_in(band, token) {
+                Some((waiting, owner)) => {
+                    self.dequeue(waiting);
+                    let penalty = self.punish_betrayal(waiting, token);
+                    self.fight(BattleKind::Matchmaking, waiting, token);
+                    let cooldown = self.cooldown_of(token, self.config.attack_cooldown);
+                    let waiting_cooldown =
+                        self.cooldown_of(waiting, self.config.attack_cooldown) + penalty;
+                    if owner == caller {
+                        self.time_constrain(caller, core::cmp::max(cooldown, waiting_cooldown));
+                    } else {
+                        self.time_constrain(owner, waiting_cooldown);
+                        self.time_constrain(caller, cooldown);
+                    }
+                }
+                None => {
+                    self.queue.insert(band, token);
+                    self.queued.insert(token, QueueEntry {
+                        owner: caller,
+                        band,
+                        entered_at: self.env().block_number(),
+                    });
+                    self.env().emit_event(Queued { token, band });
+                }
+            };
+            Ok(())
+        }
+
+        /// Takes the caller's token out of the matchmaking queue.
+        #[ink(message)]
+        pub fn leave_queue(&mut self, token: TokenId) -> Result<(), Error> {
+            if self.owner_of(token) != Some(self.env().caller()) {
+                return Err(Error::NotOwner)
+            };
+            if !self.dequeue(token) {
+                return Err(Error::NotQueued)
+            };
+            self.env().emit_event(LeftQueue { token });
+            Ok(())
+        }
+
+        /// Returns the token's place in the matchmaking queue, if it is waiting.
+        #[ink(message)]
+        pub fn queue_entry(&self, token: TokenId) -> Option<QueueEntry> {
+            self.queued.get(&token).cloned()
+        }
+
+        /// Returns the token waiting in a matchmaking band, if any.
+        #[ink(message)]
+        pub fn waiting_in(&self, band: u32) -> Option<TokenId> {
+            self.queue.get(&band).cloned()
+        }
+
+        /// Returns the matchmaking band the token would wait in.
+        #[ink(message)]
+        pub fn band_of(&self, token: TokenId) -> u32 {
+            match self.config.matchmaking {
+                Bracket::Rating => {
+                    self.rating(token) / core::cmp::max(self.config.rating_band, 1)
+                }
+                Bracket::Rank => u32::from(self.rank(token)),
+            }
+        }
+
+        ///Returns the token waiting in `band` and its owner, once it is checked to still be
+        ///fit to fight `arrival`. A stale entry is dropped from the queue instead
+        fn opponent_in(&mut self, band: u32, arrival: TokenId) -> Option<(TokenId, AccountId)> {
+            let waiting = *self.queue.get(&band)?;
+            let entry = *self.queued.get(&waiting)?;
+            self.decay(waiting);
+            if self.owner_of(waiting) == Some(entry.owner)
+                && self.is_account_allowed(entry.owner)
+                && self.band_of(waiting) == band
+                && self.ensure_can_strike(waiting, arrival).is_ok()
+            {
+                return Some((waiting, entry.owner))
+            };
+            self.dequeue(waiting);
+            self.env().emit_event(LeftQueue { token: waiting });
+            None
+        }
+
+        ///Removes the token from the queue, returning whether it was waiting
+        fn dequeue(&mut self, token: TokenId) -> bool {
+            match self.queued.take(&token) {
+                Some(entry) => {
+                    self.queue.take(&entry.band);
+                    true
+                }
+                None => false,
+            }
+        }
+    }
+
+    ///Faction war
+    impl Erc721 {
+        /// Returns the faction war score of the current season.
+        #[ink(message)]
+        pub fn faction_scores(&self) -> FactionScores {
+            self.war_scores(self.season)
+        }
+
+        /// Returns the faction war score of any season.
+        #[ink(message)]
+        pub fn war_scores(&self, season: SeasonId) -> FactionScores {
+            self.war_scores.get(&season).cloned().unwrap_or_default()
+        }
+
+        /// Returns the points the token scored for each faction in a season.
+        #[ink(message)]
+        pub fn war_contribution(&self, season: SeasonId, id: TokenId) -> FactionScores {
+            self.war_contributions
+                .get(&(season, id))
+                .cloned()
+                .unwrap_or_default()
+        }
+
+        /// Returns the funds waiting to be paid to future war winners.
+        #[ink(message)]
+        pub fn war_chest(&self) -> Balance {
+            self.war_chest
+        }
+
+        /// Adds the transferred value to the war chest.
+        #[ink(message, payable)]
+        pub fn fund_war_chest(&mut self) {
+            self.war_chest += self.env().transferred_balance();
+        }
+
+        ///Credits the winner's faction with a point when it beat a token of the other one
+        fn score_war(&mut self, winner: TokenId, loser: TokenId) {
+            let faction = self.faction_of(winner);
+            if faction == self.faction_of(loser) {
+                return
+            };
+            let season = self.season;
+            self.war_scores
+                .entry(season)
+                .or_insert_with(FactionScores::default)
+                .add(faction, 1);
+            self.war_contributions
+                .entry((season, winner))
+                .or_insert_with(FactionScores::default)
+                .add(faction, 1);
+        }
+
+        /// Returns the faction that won a season's war, none on a tie.
+        #[ink(message)]
+        pub fn war_winner(&self, season: SeasonId) -> Option<Faction> {
+            let scores = self.war_scores(season);
+            if scores.heaven > scores.fallen {
+                Some(Faction::Heaven)
+            } else if scores.fallen > scores.heaven {
+                Some(Faction::Fallen)
+            } else {
+                None
+            }
+        }
+
+        /// Pays the caller's token its share of the last ended season's war pot, in
+        /// proportion to the points it scored for the winning faction. Each share is
+        /// claimed once, before the next season ends.
+        #[ink(message)]
+        pub fn claim_war_reward(&mut self, season: SeasonId, id: TokenId) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if self.owner_of(id) != Some(caller) {
+                return Err(Error::NotOwner)
+            };
+            if season + 1 != self.season {
+                return Err(Error::WrongPhase)
+            };
+            if self.war_claims.contains_key(&(season, id)) {
+                return Err(Error::AlreadyClaimed)
+            };
+            let faction = self.war_winner(season).ok_or(Error::NoWarReward)?;
+            let points = Balance::from(self.war_contribution(season, id).of(faction));
+            if points == 0 {
+                return Err(Error::NoWarReward)
+            };
+            let total = Balance::from(self.war_scores(season).of(faction));
+            let (pot, left) = self.war_pots.get(&season).copied().unwrap_or((0, 0));
+            let reward = core::cmp::min(pot * points / total, left);
+            self.pay(caller, reward)?;
+            self.war_pots.insert(season, (pot, left - reward));
+            self.war_claims.insert((season, id), true);
+            self.env().emit_event(WarRewardClaimed {
+                season,
+                token: id,
+                reward,
+            });
+            Ok(())
+        }
+
+        ///Returns what was not claimed of the previous season's pot to the war chest, then
+        ///sets `war_share_bps` of the chest aside for the winning faction's participants
+        ///to claim. A tie leaves the chest untouched
+        fn settle_war(&mut self, season: SeasonId) {
+            if let Some(previous) = season.checked_sub(1) {
+                if let Some((_, left)) = self.war_pots.take(&previous) {
+                    self.war_chest += left;
+                };
+            };
+            let scores = self.war_scores(season);
+            let winner = self.war_winner(season);
+            let mut pot: Balance = 0;
+            if winner.is_some() {
+                pot = self.war_chest * Balance::from(self.config.war_share_bps) / 10_000;
+                self.war_chest -= pot;
+                self.war_pots.insert(season, (pot, pot));
+            };
+            self.env().emit_event(WarEnded {
+                season,
+                winner,
+                scores,
+                pot,
+            });
+        }
+    }
+
+    impl FactionScores {
+        /// Points scored by one faction.
+        pub fn of(&self, faction: Faction) -> u32 {
+            match faction {
+                Faction::Heaven => self.heaven,
+                Faction::Fallen => self.fallen,
+            }
+        }
+
+        fn add(&mut self, faction: Faction, points: u32) {
+            match faction {
+                Faction::Heaven => self.heaven += points,
+                Faction::Fallen => self.fallen += points,
+            }
+        }
+    }
+
+    impl StatusEffect {
+        /// How many times the effect can stack on one token.
+        fn max_stacks(self) -> u8 {
+            match self {
+                StatusEffect::Blessed | StatusEffect::Wounded => 3,
+                StatusEffect::Shielded | StatusEffect::Stunned | StatusEffect::Warded => 1,
+            }
+        }
+    }
+
+    /// Victories a token needs to ascend to `tier`.
+    fn ascension_victories(tier: u8) -> u64 {
+        4 << (tier - 1)
+    }
+
+    /// Orders a pair of tokens so an alliance is stored once for both directions.
+    fn alliance_key(angel: TokenId, ally: TokenId) -> (TokenId, TokenId) {
+        if angel < ally {
+            (angel, ally)
+        } else {
+            (ally, angel)
+        }
+    }
+
+    /// Champion first, then the knocked out tokens from the last to fall to the first.
+    fn placements(tournament: &Tournament) -> Vec<TokenId> {
+        tournament
+            .bracket
+            .iter()
+            .chain(tournament.eliminated.iter().rev())
+            .cloned()
+            .collect()
+    }
+
+    impl Attributes {
+        /// Rolls every attribute in `1..=MAX_ROLL` from one byte of the seed.
+        fn from_seed(seed: &[u8; 32]) -> Self {
+            let roll = |byte: u8| 1 + byte % MAX_ROLL;
+            Self {
+                might: roll(seed[0]),
+                grace: roll(seed[1]),
+                resilience: roll(seed[2]),
+                speed: roll(seed[3]),
+            }
+        }
+
+        /// Returns the attributes raised by one point each, capped at `MAX_ATTRIBUTE`.
+        fn ascended(self) -> Self {
+            let raise = |value: u8| core::cmp::min(value + 1, MAX_ATTRIBUTE);
+            Self {
+                might: raise(self.might),
+                grace: raise(self.grace),
+                resilience: raise(self.resilience),
+                speed: raise(self.speed),
+            }
+        }
+    }
+
+    impl Affinity {
+        /// Picks an affinity from the byte following the attribute rolls.
+        fn from_seed(seed: &[u8; 32]) -> Self {
+            match seed[4] % AFFINITIES as u8 {
+                0 => Affinity::Fire,
+                1 => Affinity::Light,
+                2 => Affinity::Storm,
+                _ => Affinity::Silence,
+            }
+        }
+    }
+
+    /// Expected score, in per mille, of a side rated `rating` against one rated `opponent`.
+    fn expected_score(rating: u32, opponent: u32) -> u32 {
+        let gap = if rating > opponent {
+            rating - opponent
+        } else {
+            opponent - rating
+        };
+        let step = (gap / RATING_STEP) as usize;
+        let favourite = if step + 1 >= EXPECTED_SCORE.len() {
+            EXPECTED_SCORE[EXPECTED_SCORE.len() - 1]
+        } else {
+            let rise = EXPECTED_SCORE[step + 1] - EXPECTED_SCORE[step];
+            EXPECTED_SCORE[step] + rise * (gap % RATING_STEP) / RATING_STEP
+        };
+        if rating >= opponent {
+            favourite
+        } else {
+            1000 - favourite
+        }
+    }
+
+    fn decrease_counter_of(
+        hmap: &mut StorageHashMap<AccountId, u32>,
+        of: &AccountId,
+    ) -> Result<(), Error> {
+        let count = (*hmap).get_mut(of).ok_or(Error::CannotFetchValue)?;
+        *count -= 1;
+        Ok(())
+    }
+
+    fn decrease_counter_of_tokenid(
+        hmap: &mut StorageHashMap<TokenId, u32>,
+        token: &TokenId
+    ) -> Result<(), Error> {
+        let count = (*hmap).get_mut(&token).ok_or(Error::CannotFetchValue)?;
+        *count -= 1;
+        Ok(())
+    }
+
+    /// Increase token counter from the `of` AccountId.
+    fn increase_counter_of(entry: Entry<AccountId, u32>) {
+        entry.and_modify(|v| *v += 1).or_insert(1);
+    }
+
+    fn increase_counter_of_tokenid(entry: Entry<TokenId, u32>) {
+        entry.and_modify(|v| *v += 1).or_insert(1);
+    }
+
+    /// Unit tests
+    #[cfg(test)]
+    mod tests {
+        /// Imports all the definitions from the outer scope so we can use them here.
+        use super::*;
+        use ink_env::{
+            call,
+            test,
+        };
+        use ink_lang as ink;
+
+        #[ink::test]
+        fn mint_works() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+               
+            // Create a new contract instance.
+            let mut erc721 = Erc721::new();
+            // Token 1 does not exists.
+            assert_eq!(erc721.owner_of(1), None);
+            // Alice does not owns tokens.
+            assert_eq!(erc721.balance_of(accounts.alice), 0);
+            // Create token Id 1.
+            assert_eq!(erc721.mint(), Ok(()));
+            assert_eq!(erc721.mint(), Err(Error::NotAllowed));
+            // Alice owns 1 token.
+            assert_eq!(erc721.balance_of(accounts.alice), 1);
+            assert_eq!(erc721.owner_of(0), Some(accounts.alice));
+            assert_eq!(erc721.token_owner.len(), 1);
+            //implemented to check the id assignment upon minting- passed!
+            //assert_eq!(erc721.owner_of(0), Some(accounts.alice));
+            //assert_eq!(erc721.owner_of(1), Some(accounts.alice));
+
+        }
+
+        #[ink::test]
+        fn mint_rolls_attributes_from_seed() {
+            // Create a new contract instance.
+            let mut erc721 = Erc721::new();
+            // Token 0 has no attributes before it is minted.
+            assert_eq!(erc721.attributes_of(0), None);
+            // Mint token 0 with a known seed.
+            let mut seed = [0x0; 32];
+            seed[..4].copy_from_slice(&[3, 16, 31, 255]);
+            assert_eq!(erc721.mint_with_seed(seed), Ok(()));
+            // Every attribute is rolled from its own seed byte.
+            assert_eq!(
+                erc721.attributes_of(0),
+                Some(Attributes {
+                    might: 4,
+                    grace: 1,
+                    resilience: 16,
+                    speed: 16,
+                })
+            );
+            // Burning the token removes its attributes.
+            assert_eq!(erc721.burn(0), Ok(()));
+            assert_eq!(erc721.attributes_of(0), None);
+        }
+
+        #[ink::test]
+        fn advantage_matrix_works() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // Create a new contract instance.
+            let mut erc721 = Erc721::new();
+            // Mint token 0 with the Storm affinity.
+            let mut seed = [0x0; 32];
+            seed[4] = 2;
+            assert_eq!(erc721.mint_with_seed(seed), Ok(()));
+            assert_eq!(erc721.affinity_of(0), Some(Affinity::Storm));
+            // Storm clouds Light, and Light is weak against Storm.
+            assert_eq!(erc721.advantage_of(Affinity::Storm, Affinity::Light), 4);
+            assert_eq!(erc721.advantage_of(Affinity::Light, Affinity::Storm), -4);
+            // Opposite choirs are neutral.
+            assert_eq!(erc721.advantage_of(Affinity::Storm, Affinity::Silence), 0);
+            // Only the admin can retune the matrix.
+            let mut config = erc721.config();
+            config.advantages[2][1] = 8;
+            set_sender(accounts.eve);
+            assert_eq!(erc721.set_config(config.clone()), Err(Error::NotAllowed));
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc721.set_config(config), Ok(()));
+            assert_eq!(erc721.advantage_of(Affinity::Storm, Affinity::Light), 8);
+        }
+
+        #[ink::test]
+        fn mint_existing_should_fail() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // Create a new contract instance.
+            let mut erc721 = Erc721::new();
+            // Create token Id 0.
+            assert_eq!(erc721.mint(), Ok(()));
+
+            // The first Transfer event takes place
+            assert_eq!(1, ink_env::test::recorded_events().count());
+            // Alice owns 1 token.
+            assert_eq!(erc721.balance_of(accounts.alice), 1);
+            // Alice owns token Id 1.
+            assert_eq!(erc721.owner_of(0), Some(accounts.alice));
+            //Should not allow caller to mint twice 
+            assert_eq!(erc721.mint(), Err(Error::NotAllowed));
+        }
+
+        #[ink::test]
+        fn transfer_works() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // Create a new contract instance.
+            let mut erc721 = Erc721::new();
+            // Create token Id 1 for Alice
+            assert_eq!(erc721.mint(), Ok(()));
+            // Alice owns token 1
+            assert_eq!(erc721.balance_of(accounts.alice), 1);
+            // Bob does not owns any token
+            assert_eq!(erc721.balance_of(accounts.bob), 0);
+            // The first Transfer event takes place
+            assert_eq!(1, ink_env::test::recorded_events().count());
+            // Alice transfers token 1 to Bob
+            assert_eq!(erc721.transfer(accounts.bob, 0), Ok(()));
+            // The second Transfer event takes place
+            assert_eq!(2, ink_env::test::recorded_events().count());
+            // Bob owns token 1
+            assert_eq!(erc721.balance_of(accounts.bob), 1);
+        }
+
+        #[ink::test]
+        fn invalid_transfer_should_fail() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // Create a new contract instance.
+            let mut erc721 = Erc721::new();
+            // Transfer token fails if it does not exists.
+            assert_eq!(erc721.transfer(accounts.bob, 2), Err(Error::TokenNotFound));
+            // Token Id 2 does not exists.
+            assert_eq!(erc721.owner_of(2), None);
+            // Create token Id 2.
+            assert_eq!(erc721.mint(), Ok(()));
+            // Alice owns 1 token.
+            assert_eq!(erc721.balance_of(accounts.alice), 1);
+           // Get contract address
+            let callee = ink_env::account_id::<ink_env::DefaultEnvironment>()
+                .unwrap_or([0x0; 32].into());
+            // Create call
+            let mut data =
+                ink_env::test::CallData::new(ink_env::call::Selector::new([0x00; 4])); // balance_of
+            data.push_arg(&accounts.bob);
+            // Push the new execution context to set Bob as caller
+            ink_env::test::push_execution_context::<ink_env::DefaultEnvironment>(
+                accounts.bob,
+                callee,
+                1000000,
+                1000000,
+                data,
+            );
+            // Bob cannot transfer not owned tokens.
+            assert_eq!(erc721.transfer(accounts.eve, 2), Err(Error::NotApproved));
+        }
+
+        #[ink::test]
+        fn approved_transfer_works() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // Create a new contract instance.
+            let mut erc721 = Erc721::new();
+            // Create token Id 1.
+            assert_eq!(erc721.mint(), Ok(()));
+            // Token Id 1 is owned by Alice.
+            assert_eq!(erc721.owner_of(0), Some(accounts.alice));
+            // Approve token Id 1 transfer for Bob on behalf of Alice.
+            assert_eq!(erc721.approve(accounts.bob, 0), Ok(()));
+            // Get contract address.
+            let callee = ink_env::account_id::<ink_env::DefaultEnvironment>()
+                .unwrap_or([0x0; 32].into());
+            // Create call
+            let mut data =
+                ink_env::test::CallData::new(ink_env::call::Selector::new([0x00; 4])); // balance_of
+            data.push_arg(&accounts.bob);
+            // Push the new execution context to set Bob as caller
+            ink_env::test::push_execution_context::<ink_env::DefaultEnvironment>(
+                accounts.bob,
+                callee,
+                1000000,
+                1000000,
+                data,
+            );
+            // Bob transfers token Id 1 from Alice to Eve.
+            assert_eq!(
+                erc721.transfer_from(accounts.alice, accounts.eve, 0),
+                Ok(())
+            );
+            // TokenId 3 is owned by Eve.
+            assert_eq!(erc721.owner_of(0), Some(accounts.eve));
+            // Alice does not owns tokens.
+            assert_eq!(erc721.balance_of(accounts.alice), 0);
+            // Bob does not owns tokens.
+            assert_eq!(erc721.balance_of(accounts.bob), 0);
+            // Eve owns 1 token.
+            assert_eq!(erc721.balance_of(accounts.eve), 1);
+        }
+
+        #[ink::test]
+        fn approved_for_all_works() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // Create a new contract instance.
+            let mut erc721 = Erc721::new();
+            // Create token Id 1.
+            assert_eq!(erc721.mint(), Ok(()));
+            // Create token Id 2.
+            assert_eq!(erc721.mint(), Ok(()));
+            // Alice owns 2 tokens.
+            assert_eq!(erc721.balance_of(accounts.alice), 2);
+            // Approve token Id 1 transfer for Bob on behalf of Alice.
+            assert_eq!(erc721.set_approval_for_all(accounts.bob, true), Ok(()));
+            // Bob is an approved operator for Alice
+            assert_eq!(
+                erc721.is_approved_for_all(accounts.alice, accounts.bob),
+                true
+            );
+            // Get contract address.
+            let callee = ink_env::account_id::<ink_env::DefaultEnvironment>()
+                .unwrap_or([0x0; 32].into());
+            // Create call
+            let mut data =
+                ink_env::test::CallData::new(ink_env::call::Selector::new([0x00; 4])); // balance_of
+            data.push_arg(&accounts.bob);
+            // Push the new execution context to set Bob as caller
+            ink_env::test::push_execution_context::<ink_env::DefaultEnvironment>(
+                accounts.bob,
+                callee,
+                1000000,
+                1000000,
+                data,
+            );
+            // Bob transfers token Id 1 from Alice to Eve.
+            assert_eq!(
+                erc721.transfer_from(accounts.alice, accounts.eve, 1),
+                Ok(())
+            );
+            // TokenId 1 is owned by Eve.
+            assert_eq!(erc721.owner_of(1), Some(accounts.eve));
+            // Alice owns 1 token.
+            assert_eq!(erc721.balance_of(accounts.alice), 1);
+            // Bob transfers token Id 2 from Alice to Eve.
+            assert_eq!(
+                erc721.transfer_from(accounts.alice, accounts.eve, 2),
+                Ok(())
+            );
+            // Bob does not owns tokens.
+            assert_eq!(erc721.balance_of(accounts.bob), 0);
+            // Eve owns 2 tokens.
+            assert_eq!(erc721.balance_of(accounts.eve), 2);
+            // Get back to the parent execution context.
+            ink_env::test::pop_execution_context();
+            // Remove operator approval for Bob on behalf of Alice.
+            assert_eq!(erc721.set_approval_for_all(accounts.bob, false), Ok(()));
+            // Bob is not an approved operator for Alice.
+            assert_eq!(
+                erc721.is_approved_for_all(accounts.alice, accounts.bob),
+                false
+            );
+        }
+
+        #[ink::test]
+        fn not_approved_transfer_should_fail() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // Create a new contract instance.
+            let mut erc721 = Erc721::new();
+            // Create token Id 1.
+            assert_eq!(erc721.mint(), Ok(()));
+            // Alice owns 1 token.
+            assert_eq!(erc721.balance_of(accounts.alice), 1);
+            // Bob does not owns tokens.
+            assert_eq!(erc721.balance_of(accounts.bob), 0);
+            // Eve does not owns tokens.
+            assert_eq!(erc721.balance_of(accounts.eve), 0);
+            // Get contract address.
+            let callee = ink_env::account_id::<ink_env::DefaultEnvironment>()
+                .unwrap_or([0x0; 32].into());
+            // Create call
+            let mut data =
+                ink_env::test::CallData::new(ink_env::call::Selector::new([0x00; 4])); // balance_of
+            data.push_arg(&accounts.bob);
+            // Push the new execution context to set Eve as caller
+            ink_env::test::push_execution_context::<ink_env::DefaultEnvironment>(
+                accounts.eve,
+                callee,
+                1000000,
+                1000000,
+                data,
+            );
+            // Eve is not an approved operator by Alice.
+            assert_eq!(
+                erc721.transfer_from(accounts.alice, accounts.frank, 0),
+                Err(Error::NotApproved)
+            );
+            // Alice owns 1 token.
+            assert_eq!(erc721.balance_of(accounts.alice), 1);
+            // Bob does not owns tokens.
+            assert_eq!(erc721.balance_of(accounts.bob), 0);
+            // Eve does not owns tokens.
+            assert_eq!(erc721.balance_of(accounts.eve), 0);
+        }
+
+        #[ink::test]
+        fn burn_works() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // Create a new contract instance.
+            let mut erc721 = Erc721::new();
+            // Create token Id 1 for Alice
+            assert_eq!(erc721.mint(), Ok(()));
+            // Alice owns 1 token.
+            assert_eq!(erc721.balance_of(accounts.alice), 1);
+            // Alice owns token Id 1.
+            assert_eq!(erc721.owner_of(0), Some(accounts.alice));
+            // Destroy token Id 1.
+            assert_eq!(erc721.burn(0), Ok(()));
+            // Alice does not owns tokens.
+            assert_eq!(erc721.balance_of(accounts.alice), 0);
+            // Token Id 1 does not exists
+            assert_eq!(erc721.owner_of(1), None);
+        }
+
+        #[ink::test]
+        fn burn_fails_token_not_found() {
+            // Create a new contract instance.
+            let mut erc721 = Erc721::new();
+            // Try burning a non existent token
+            assert_eq!(erc721.burn(1), Err(Error::TokenNotFound));
+        }
+
+        #[ink::test]
+        fn burn_fails_not_owner() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // Create a new contract instance.
+            let mut erc721 = Erc721::new();
+            // Create token Id 1 for Alice
+            assert_eq!(erc721.mint(), Ok(()));
+            // Try burning this token with a different account
+            set_sender(accounts.eve);
+            assert_eq!(erc721.burn(0), Err(Error::NotOwner));
+        }
+
+        #[ink::test]
+        fn attack_records_loss_at_once() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // Create a new contract instance.
+            let mut erc721 = Erc721::new();
+            // Alice mints a mighty token 0, Bob a resilient token 1.
+            mint_duelists(&mut erc721, accounts.bob);
+            // A quick attack records the loss straight away.
+            assert_eq!(erc721.attack(0, 1), Ok(()));
+            assert_eq!(erc721.victories_count(0), 1);
+            assert_eq!(erc721.losses_count(1), 1);
+            // Health is left untouched.
+            assert_eq!(erc721.health_of(1), Some(180));
+            // Alice is cooling down now.
+            assert_eq!(erc721.attack(0, 1), Err(Error::NotAllowed));
+        }
+
+        #[ink::test]
+        fn improved_attack_wears_victim_down() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // Create a new contract instance.
+            let mut erc721 = Erc721::new();
+            mint_duelists(&mut erc721, accounts.bob);
+            // Plain angels cannot use the heavy attack.
+            assert_eq!(erc721.improved_attack(0, 1), Err(Error::NotAllowed));
+            erc721.principality.insert(0, true);
+            // Shorten the heavy cooldown so several hits fit in the test.
+            let mut config = erc721.config();
+            config.heavy_cooldown = 1;
+            assert_eq!(erc721.set_config(config), Ok(()));
+            // Each hit deals 48 - 16 = 32 damage to a health of 100 + 16 * 5.
+            for health in [148, 116, 84, 52, 20].iter() {
+                assert_eq!(erc721.improved_attack(0, 1), Ok(()));
+                assert_eq!(erc721.health_of(1), Some(*health));
+                assert_eq!(erc721.losses_count(1), 0);
+                ink_env::test::advance_block::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot advance block");
+            }
+            // The sixth hit knocks token 1 down and restores its health.
+            assert_eq!(erc721.improved_attack(0, 1), Ok(()));
+            assert_eq!(erc721.losses_count(1), 1);
+            assert_eq!(erc721.victories_count(0), 1);
+            assert_eq!(erc721.health_of(1), Some(180));
+        }
+
+        #[ink::test]
+        fn rating_moves_with_each_battle() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // Create a new contract instance.
+            let mut erc721 = Erc721::new();
+            mint_duelists(&mut erc721, accounts.bob);
+            // Everyone starts from the base rating.
+            assert_eq!(erc721.rating_of(0), 1200);
+            assert_eq!(erc721.rating_of(1), 1200);
+            let mut config = erc721.config();
+            config.attack_cooldown = 1;
+            assert_eq!(erc721.set_config(config), Ok(()));
+            // An even match moves half of K.
+            assert_eq!(erc721.attack(0, 1), Ok(()));
+            assert_eq!(erc721.rating_of(0), 1216);
+            assert_eq!(erc721.rating_of(1), 1184);
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>()
+                .expect("Cannot advance block");
+            // Beating a lower rated token pays less: 32 * (1000 - 545) / 1000.
+            assert_eq!(erc721.attack(0, 1), Ok(()));
+            assert_eq!(erc721.rating_of(0), 1230);
+            assert_eq!(erc721.rating_of(1), 1170);
+        }
+
+        #[ink::test]
+        fn expected_score_is_symmetric() {
+            assert_eq!(expected_score(1200, 1200), 500);
+            assert_eq!(expected_score(1400, 1200), 760);
+            assert_eq!(expected_score(1200, 1400), 240);
+            // Gaps beyond the table are capped.
+            assert_eq!(expected_score(3000, 0), 990);
+        }
+
+        #[ink::test]
+        fn leaderboard_follows_scores() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // Create a new contract instance.
+            let mut erc721 = Erc721::new();
+            mint_duelists(&mut erc721, accounts.bob);
+            // Charlie mints the weakest possible token 2.
+            set_sender(accounts.charlie);
+            assert_eq!(erc721.mint_with_seed([0x0; 32]), Ok(()));
+            ink_env::test::pop_execution_context();
+            // Keep only two places on the board.
+            let mut config = erc721.config();
+            config.leaderboard_size = 2;
+            config.attack_cooldown = 1;
+            assert_eq!(erc721.set_config(config), Ok(()));
+            // Token 0 beats token 1.
+            assert_eq!(erc721.attack(0, 1), Ok(()));
+            assert_eq!(erc721.leaderboard(), vec![(0, 1), (1, 0)]);
+            // Token 2 loses to token 1, which ties with token 0 behind the lower id.
+            set_sender(accounts.charlie);
+            assert_eq!(erc721.attack(2, 1), Ok(()));
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc721.leaderboard(), vec![(0, 1), (1, 1)]);
+            // Transfers do not move tokens on the board.
+            assert_eq!(erc721.transfer(accounts.django, 0), Ok(()));
+            assert_eq!(erc721.leaderboard(), vec![(0, 1), (1, 1)]);
+            // Burnt tokens leave the board, and anyone can offer a token to fill their place.
+            set_sender(accounts.bob);
+            assert_eq!(erc721.burn(1), Ok(()));
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc721.leaderboard(), vec![(0, 1)]);
+            erc721.refresh_leaderboard(vec![2, 7]);
+            assert_eq!(erc721.leaderboard(), vec![(0, 1), (2, 0)]);
+            // Token 2 keeps its place after losing again.
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>()
+                .expect("Cannot advance block");
+            set_sender(accounts.charlie);
+            assert_eq!(erc721.attack(2, 0), Ok(()));
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc721.leaderboard(), vec![(0, 2), (2, 0)]);
+        }
+
+        #[ink::test]
+        fn battle_history_pages_newest_first() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // Create a new contract instance.
+            let mut erc721 = Erc721::new();
+            mint_duelists(&mut erc721, accounts.bob);
+            // Keep the last two battles only.
+            let mut config = erc721.config();
+            config.attack_cooldown = 1;
+            config.history_size = 2;
+            assert_eq!(erc721.set_config(config), Ok(()));
+            // Fight three times, one block apart.
+            for _ in 0..3 {
+                assert_eq!(erc721.attack(0, 1), Ok(()));
+                ink_env::test::advance_block::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot advance block");
+            }
+            let record = |opponent, won, block| {
+                BattleRecord {
+                    opponent,
+                    kind: BattleKind::Attack,
+                    won,
+                    block,
+                }
+            };
+            // The oldest battle has been overwritten.
+            assert_eq!(
+                erc721.battle_history(0, 0, 10),
+                vec![record(1, true, 2), record(1, true, 1)]
+            );
+            // The defender sees the same battles from its side.
+            assert_eq!(erc721.battle_history(1, 1, 1), vec![record(0, false, 1)]);
+            // Paging past the end is empty.
+            assert_eq!(erc721.battle_history(1, 2, 1), vec![]);
+        }
+
+        #[ink::test]
+        fn duel_can_be_accepted_or_declined() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // Create a new contract instance.
+            let mut erc721 = Erc721::new();
+            mint_duelists(&mut erc721, accounts.bob);
+            let mut config = erc721.config();
+            config.attack_cooldown = 1;
+            assert_eq!(erc721.set_config(config), Ok(()));
+            // Nothing is staked.
+            set_sender_with_value(accounts.alice, 0);
+            // A challenge has to stay open for at least a block.
+            assert_eq!(erc721.challenge(0, 1, 0), Err(Error::InvalidExpiry));
+            // Alice challenges Bob's token for the next 10 blocks.
+            assert_eq!(erc721.challenge(0, 1, 10), Ok(()));
+            assert_eq!(
+                erc721.challenge_of(0, 1),
+                Some(Challenge {
+                    expires_at: 10,
+                    ranked: false,
+                    stake: 0,
+                    staker: accounts.alice,
+                })
+            );
+            // Only Bob can answer it.
+            assert_eq!(erc721.accept_challenge(0, 1), Err(Error::NotOwner));
+            set_sender_with_value(accounts.bob, 0);
+            assert_eq!(erc721.accept_challenge(0, 1), Ok(()));
+            assert_eq!(erc721.challenge_of(0, 1), None);
+            assert_eq!(erc721.victories_count(0), 1);
+            assert_eq!(erc721.losses_count(1), 1);
+            // Both duellists are on cooldown now, so neither can challenge again at once.
+            assert_eq!(erc721.challenge(1, 0, 10), Err(Error::NotAllowed));
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc721.challenge(0, 1, 10), Err(Error::NotAllowed));
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>()
+                .expect("Cannot advance block");
+            // Bob's token challenges back for as long as possible, then Alice turns it down.
+            set_sender_with_value(accounts.bob, 0);
+            assert_eq!(erc721.challenge(1, 0, BlockNumber::MAX), Ok(()));
+            assert_eq!(erc721.challenge_of(1, 0).unwrap().expires_at, BlockNumber::MAX);
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc721.decline_challenge(1, 0), Ok(()));
+            assert_eq!(erc721.challenge_of(1, 0), None);
+            assert_eq!(erc721.accept_challenge(1, 0), Err(Error::ChallengeNotFound));
+            assert_eq!(erc721.victories_count(1), 0);
+        }
+
+        #[ink::test]
+        fn ranked_challenge_is_forfeited_when_ignored() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // Create a new contract instance.
+            let mut erc721 = Erc721::new();
+            mint_duelists(&mut erc721, accounts.bob);
+            let mut config = erc721.config();
+            config.ranked_duels = true;
+            // A ranked challenge has to stay open for at least a block.
+            config.forfeit_blocks = 0;
+            assert_eq!(erc721.set_config(config.clone()), Err(Error::InvalidConfig));
+            config.forfeit_blocks = 2;
+            assert_eq!(erc721.set_config(config), Ok(()));
+            // Ranked challenges use the forfeit window whatever expiry is asked for.
+            set_sender_with_value(accounts.alice, 0);
+            assert_eq!(erc721.challenge(0, 1, 100), Ok(()));
+            assert_eq!(erc721.expire_challenge(0, 1), Err(Error::NotAllowed));
+            for _ in 0..2 {
+                ink_env::test::advance_block::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot advance block");
+            }
+            // Too late to accept now.
+            set_sender_with_value(accounts.bob, 0);
+            assert_eq!(erc721.accept_challenge(0, 1), Err(Error::ChallengeExpired));
+            // Anyone can clear it, and Bob's token forfeits.
+            assert_eq!(erc721.expire_challenge(0, 1), Ok(()));
+            assert_eq!(erc721.losses_count(1), 1);
+            assert_eq!(erc721.victories_count(0), 1);
+            assert_eq!(erc721.challenge_of(0, 1), None);
+        }
+
+        #[ink::test]
+        fn wagered_duel_pays_the_winner() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // Create a new contract instance.
+            let mut erc721 = Erc721::new();
+            mint_duelists(&mut erc721, accounts.bob);
+            assert_eq!(erc721.set_treasury(accounts.django), Ok(()));
+            // The house cannot take more than the whole pot.
+            let mut config = erc721.config();
+            config.house_fee_bps = 10_001;
+            assert_eq!(erc721.set_config(config), Err(Error::InvalidConfig));
+            let alice_before = balance_of(accounts.alice);
+            let django_before = balance_of(accounts.django);
+            let escrow_before = balance_of(contract_id());
+            // Alice stakes 1000 on a duel.
+            set_sender_paying(accounts.alice, 1000);
+            assert_eq!(erc721.challenge(0, 1, 10), Ok(()));
+            // Bob has to match the stake exactly.
+            set_sender_with_value(accounts.bob, 999);
+            assert_eq!(erc721.accept_challenge(0, 1), Err(Error::WrongStake));
+            ink_env::test::pop_execution_context();
+            // Both stakes sit in the contract's escrow.
+            set_sender_paying(accounts.bob, 1000);
+            assert_eq!(balance_of(contract_id()), escrow_before + 2000);
+            assert_eq!(erc721.accept_challenge(0, 1), Ok(()));
+            // Alice's token wins the pot minus 2.5% for the treasury.
+            assert_eq!(balance_of(accounts.alice), alice_before - 1000 + 1950);
+            assert_eq!(balance_of(accounts.django), django_before + 50);
+            assert_eq!(balance_of(contract_id()), escrow_before);
+        }
+
+        #[ink::test]
+        fn expired_wager_is_refunded() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // Create a new contract instance.
+            let mut erc721 = Erc721::new();
+            mint_duelists(&mut erc721, accounts.bob);
+            let alice_before = balance_of(accounts.alice);
+            let django_before = balance_of(accounts.django);
+            let escrow_before = balance_of(contract_id());
+            // Alice stakes 1000 on a duel that Bob never answers.
+            set_sender_paying(accounts.alice, 1000);
+            assert_eq!(erc721.challenge(0, 1, 1), Ok(()));
+            assert_eq!(balance_of(accounts.alice), alice_before - 1000);
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>()
+                .expect("Cannot advance block");
+            // Once expired, the stake goes back to Alice even though she sold her token.
+            assert_eq!(erc721.transfer(accounts.django, 0), Ok(()));
+            assert_eq!(erc721.expire_challenge(0, 1), Ok(()));
+            assert_eq!(balance_of(accounts.alice), alice_before);
+            assert_eq!(balance_of(accounts.django), django_before);
+            assert_eq!(balance_of(contract_id()), escrow_before);
+            assert_eq!(erc721.losses_count(1), 0);
+        }
+
+        #[ink::test]
+        fn tournament_pays_top_placements() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // Create a new contract instance.
+            let mut erc721 = Erc721::new();
+            mint_duelists(&mut erc721, accounts.bob);
+            // Charlie and Django mint the weakest possible tokens 2 and 3.
+            for account in [accounts.charlie, accounts.django].iter() {
+                set_sender(*account);
+                assert_eq!(erc721.mint_with_seed([0x0; 32]), Ok(()));
+                ink_env::test::pop_execution_context();
+            }
+            // Prize shares cannot add up to more than the pool.
+            let mut config = erc721.config();
+            config.prize_shares_bps = [6000, 3000, 1001];
+            assert_eq!(erc721.set_config(config), Err(Error::InvalidConfig));
+            // Only the admin opens tournaments.
+            set_sender(accounts.bob);
+            assert_eq!(erc721.create_tournament(100, 4, 5), Err(Error::NotAllowed));
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc721.create_tournament(100, 4, 5), Ok(0));
+            // Every owner enters with the exact fee.
+            let owners = [accounts.alice, accounts.bob, accounts.charlie, accounts.django];
+            for (token, owner) in owners.iter().enumerate() {
+                set_sender_paying(*owner, 100);
+                assert_eq!(erc721.enter_tournament(0, token as TokenId), Ok(()));
+                ink_env::test::pop_execution_context();
+            }
+            // Registration is still open.
+            assert_eq!(erc721.seed_tournament(0), Err(Error::WrongPhase));
+            for _ in 0..5 {
+                ink_env::test::advance_block::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot advance block");
+            }
+            // Equal ratings seed by token id.
+            assert_eq!(erc721.seed_tournament(0), Ok(()));
+            assert_eq!(erc721.tournament(0).unwrap().bracket, vec![0, 1, 2, 3]);
+            // Round one: 0 beats 3 and 1 holds off 2. Being stunned or shielded spares
+            // neither of them the match.
+            erc721.apply_effect(3, StatusEffect::Stunned, 1);
+            erc721.apply_effect(2, StatusEffect::Shielded, 1);
+            assert_eq!(erc721.play_round(0), Ok(()));
+            assert_eq!(erc721.tournament(0).unwrap().bracket, vec![0, 1]);
+            assert_eq!(erc721.losses_count(3), 1);
+            assert_eq!(erc721.losses_count(2), 1);
+            // The final pays 60/30/10 of the pool.
+            let before: Vec<Balance> = owners.iter().map(|owner| balance_of(*owner)).collect();
+            assert_eq!(erc721.play_round(0), Ok(()));
+            assert_eq!(erc721.tournament(0).unwrap().phase, TournamentPhase::Finished);
+            assert_eq!(erc721.tournament_placements(0), vec![0, 1, 2, 3]);
+            assert_eq!(balance_of(accounts.alice), before[0] + 240);
+            assert_eq!(balance_of(accounts.bob), before[1] + 120);
+            assert_eq!(balance_of(accounts.charlie), before[2] + 40);
+            assert_eq!(balance_of(accounts.django), before[3]);
+            // Nothing is left to fight.
+            assert_eq!(erc721.play_round(0), Err(Error::WrongPhase));
+        }
+
+        #[ink::test]
+        fn season_rollover_archives_and_resets() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // Create a new contract instance.
+            let mut erc721 = Erc721::new();
+            mint_duelists(&mut erc721, accounts.bob);
+            let mut config = erc721.config();
+            config.season_rank_decay = 2;
+            assert_eq!(erc721.set_config(config), Ok(()));
+            // Token 0 is a Power and beats token 1 once.
+            erc721.set_rank(0, POWER);
+            assert_eq!(erc721.attack(0, 1), Ok(()));
+            // Only the admin can end the season.
+            set_sender(accounts.bob);
+            assert_eq!(erc721.end_season(), Err(Error::NotAllowed));
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc721.end_season(), Ok(()));
+            assert_eq!(erc721.current_season(), 1);
+            // Last season's record is archived...
+            assert_eq!(
+                erc721.season_stats(0, 0),
+                SeasonStats {
+                    victories: 1,
+                    losses: 0,
+                }
+            );
+            assert_eq!(erc721.season_stats(0, 1).losses, 1);
+            // ...and the live counters start over.
+            assert_eq!(erc721.victories_count(0), 0);
+            assert_eq!(erc721.losses_count(1), 0);
+            assert_eq!(erc721.leaderboard(), vec![]);
+            // The Power already reads as an Archangel, two tiers down...
+            assert_eq!(erc721.rank_of(0), ARCHANGEL);
+            assert_eq!(erc721.is_power(0), false);
+            assert_eq!(erc721.is_archangel(0), true);
+            assert_eq!(erc721.rank_holders(POWER), vec![]);
+            assert_eq!(erc721.rank_holders(ARCHANGEL), vec![0]);
+            // ...and drops there once it is next touched, by the decay in force when
+            // the season ended.
+            let mut config = erc721.config();
+            config.season_rank_decay = 0;
+            assert_eq!(erc721.set_config(config), Ok(()));
+            assert_eq!(erc721.apply_decay(vec![0]), Ok(()));
+            assert_eq!(erc721.season_stats(0, 0).victories, 1);
+            assert_eq!(erc721.rank_of(0), ARCHANGEL);
+            assert_eq!(erc721.is_power(0), false);
+            assert_eq!(erc721.rank(0), ARCHANGEL);
+        }
+
+        #[ink::test]
+        fn guild_membership_works() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // Create a new contract instance.
+            let mut erc721 = Erc721::new();
+            mint_duelists(&mut erc721, accounts.bob);
+            let mut config = erc721.config();
+            config.max_guild_size = 2;
+            config.protection_blocks = 1;
+            assert_eq!(erc721.set_config(config), Ok(()));
+            set_sender(accounts.charlie);
+            assert_eq!(erc721.mint_with_seed([0x0; 32]), Ok(()));
+            ink_env::test::pop_execution_context();
+            // Alice's token founds a guild and Bob's joins it once invited.
+            assert_eq!(erc721.create_guild(0), Ok(0));
+            set_sender(accounts.bob);
+            assert_eq!(erc721.join_guild(0, 1), Err(Error::NotInvited));
+            assert_eq!(erc721.invite_to_guild(1, 1), Err(Error::NotOwner));
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc721.invite_to_guild(0, 1), Ok(()));
+            assert_eq!(erc721.is_invited(0, 1), true);
+            set_sender(accounts.bob);
+            assert_eq!(erc721.join_guild(0, 1), Ok(()));
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc721.is_invited(0, 1), false);
+            assert_eq!(erc721.members_of(0), vec![0, 1]);
+            assert_eq!(erc721.guild_of(1), Some(0));
+            // The guild is full.
+            assert_eq!(erc721.invite_to_guild(0, 2), Ok(()));
+            set_sender(accounts.charlie);
+            assert_eq!(erc721.join_guild(0, 2), Err(Error::GuildFull));
+            ink_env::test::pop_execution_context();
+            // Only the leader can kick.
+            set_sender(accounts.bob);
+            assert_eq!(erc721.kick(1, 0), Err(Error::NotAllowed));
+            ink_env::test::pop_execution_context();
+            // Charlie's token is still new.
+            assert_eq!(erc721.gangel_bangel(0, 1, 2), Err(Error::Protected));
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>()
+                .expect("Cannot advance block");
+            // Guild mates can gang up on a token, taking a victory each.
+            assert_eq!(erc721.gangel_bangel(0, 1, 2), Ok(()));
+            assert_eq!(erc721.losses_count(2), 2);
+            assert_eq!(erc721.victories_count(0), 1);
+            assert_eq!(erc721.victories_count(1), 1);
+            // A burnt token's invitation is not inherited by the next token minted with its id.
+            set_sender(accounts.charlie);
+            assert_eq!(erc721.burn(2), Ok(()));
+            ink_env::test::pop_execution_context();
+            set_sender(accounts.django);
+            assert_eq!(erc721.mint_with_seed([0x0; 32]), Ok(()));
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc721.owner_of(2), Some(accounts.django));
+            assert_eq!(erc721.is_invited(0, 2), false);
+            // Token 1 is out.
+            assert_eq!(erc721.kick(0, 1), Ok(()));
+            assert_eq!(erc721.guild_of(1), None);
+            // The last member leaving disbands the guild.
+            assert_eq!(erc721.leave_guild(0), Ok(()));
+            assert_eq!(erc721.guild(0), None);
+            assert_eq!(erc721.members_of(0), vec![]);
+        }
+
+        #[ink::test]
+        fn alliance_needs_consent() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // Create a new contract instance.
+            let mut erc721 = Erc721::new();
+            mint_duelists(&mut erc721, accounts.bob);
+            erc721.set_rank(0, DOMINION);
+            erc721.set_rank(1, DOMINION);
+            // A proposal must stay open for at least one block.
+            assert_eq!(erc721.propose_alliance(0, 1, 0), Err(Error::InvalidExpiry));
+            // Alice's token invites Bob's for the next 10 blocks.
+            assert_eq!(erc721.propose_alliance(0, 1, 10), Ok(()));
+            assert_eq!(erc721.alliance_proposal(0, 1), Some(10));
+            // Nothing binds Bob's token until its owner agrees.
+            assert_eq!(erc721.is_allied(0, 1), false);
+            assert_eq!(erc721.accept_alliance(0, 1), Err(Error::NotOwner));
+            set_sender(accounts.bob);
+            assert_eq!(erc721.accept_alliance(0, 1), Ok(()));
+            ink_env::test::pop_execution_context();
+            // The alliance holds both ways.
+            assert_eq!(erc721.is_allied(0, 1), true);
+            assert_eq!(erc721.is_allied(1, 0), true);
+            assert_eq!(erc721.alliance_proposal(0, 1), None);
+        }
+
+        #[ink::test]
+        fn alliance_can_expire_or_be_rejected() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // Create a new contract instance.
+            let mut erc721 = Erc721::new();
+            mint_duelists(&mut erc721, accounts.bob);
+            erc721.set_rank(0, DOMINION);
+            // Alice's token invites Bob's for a single block.
+            assert_eq!(erc721.propose_alliance(0, 1, 1), Ok(()));
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>()
+                .expect("Cannot advance block");
+            // The invitation has lapsed.
+            set_sender(accounts.bob);
+            assert_eq!(erc721.accept_alliance(0, 1), Err(Error::ProposalExpired));
+            assert_eq!(erc721.is_allied(0, 1), false);
+            // Bob clears it by rejecting.
+            assert_eq!(erc721.reject_alliance(0, 1), Ok(()));
+            assert_eq!(erc721.alliance_proposal(0, 1), None);
+            assert_eq!(erc721.reject_alliance(0, 1), Err(Error::ProposalNotFound));
+        }
+
+        #[ink::test]
+        fn alliances_form_reform_and_dissolve() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // Create a new contract instance.
+            let mut erc721 = Erc721::new();
+            mint_duelists(&mut erc721, accounts.bob);
+            erc721.set_rank(0, DOMINION);
+            // Both sides must be Dominions.
+            assert_eq!(erc721.propose_alliance(0, 1, 10), Ok(()));
+            set_sender(accounts.bob);
+            assert_eq!(erc721.accept_alliance(0, 1), Err(Error::NotAllowed));
+            erc721.set_rank(1, THRONE);
+            assert_eq!(erc721.accept_alliance(0, 1), Ok(()));
+            assert_eq!(erc721.allies_of(0), vec![1]);
+            assert_eq!(erc721.allies_of(1), vec![0]);
+            // Forming it again from the other side changes nothing.
+            assert_eq!(erc721.propose_alliance(1, 0, 10), Ok(()));
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc721.accept_alliance(1, 0), Ok(()));
+            assert_eq!(erc721.allies_of(0), vec![1]);
+            assert_eq!(erc721.allies_of(1), vec![0]);
+            // Either side can dissolve it, but only once.
+            assert_eq!(erc721.break_alliance(0, 1), Ok(()));
+            assert_eq!(erc721.is_allied(1, 0), false);
+            assert_eq!(erc721.allies_of(0), vec![]);
+            assert_eq!(erc721.allies_of(1), vec![]);
+            assert_eq!(erc721.break_alliance(0, 1), Err(Error::CannotRemove));
+            // Only owners can break their tokens' alliances.
+            assert_eq!(erc721.break_alliance(1, 0), Err(Error::NotOwner));
+        }
+
+        #[ink::test]
+        fn alliances_can_be_reformed_once_dissolved() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // Create a new contract instance.
+            let mut erc721 = Erc721::new();
+            mint_duelists(&mut erc721, accounts.bob);
+            erc721.set_rank(0, DOMINION);
+            erc721.set_rank(1, DOMINION);
+            assert_eq!(erc721.propose_alliance(0, 1, 10), Ok(()));
+            set_sender(accounts.bob);
+            assert_eq!(erc721.accept_alliance(0, 1), Ok(()));
+            ink_env::test::pop_execution_context();
+            // Alice dissolves the alliance.
+            assert_eq!(erc721.break_alliance(0, 1), Ok(()));
+            assert_eq!(erc721.is_allied(0, 1), false);
+            // Bob's token proposes it anew and Alice accepts.
+            set_sender(accounts.bob);
+            assert_eq!(erc721.propose_alliance(1, 0, 10), Ok(()));
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc721.accept_alliance(1, 0), Ok(()));
+            // The alliance holds again, both ways.
+            assert_eq!(erc721.is_allied(0, 1), true);
+            assert_eq!(erc721.is_allied(1, 0), true);
+            assert_eq!(erc721.allies_of(0), vec![1]);
+            assert_eq!(erc721.allies_of(1), vec![0]);
+            assert_eq!(erc721.alliance_proposal(1, 0), None);
+        }
+
+        #[ink::test]
+        fn attacking_an_ally_is_a_betrayal() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // Create a new contract instance.
+            let mut erc721 = Erc721::new();
+            mint_duelists(&mut erc721, accounts.bob);
+            erc721.set_rank(0, DOMINION);
+            erc721.set_rank(1, DOMINION);
+            assert_eq!(erc721.ally(0, 1, true), Ok(()));
+            // Alice's token turns on its ally.
+            assert_eq!(erc721.attack(0, 1), Ok(()));
+            // The alliance is gone.
+            assert_eq!(erc721.is_allied(0, 1), false);
+            assert_eq!(erc721.allies_of(0), vec![]);
+            // The betrayer still won the fight but carries an extra loss...
+            assert_eq!(erc721.victories_count(0), 1);
+            assert_eq!(erc721.losses_count(0), 1);
+            // ...a rating hit: 1200 - 25, plus 32 * (1000 - 465) / 1000 for the win...
+            assert_eq!(erc721.rating_of(0), 1192);
+            // ...and a longer cooldown: 7200 less 2% for speed, plus 7200.
+            assert_eq!(erc721.is_ready(accounts.alice), 7056 + 7200);
+        }
+
+        #[ink::test]
+        fn raid_shares_rewards_among_comrades() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // Create a new contract instance.
+            let mut erc721 = Erc721::new();
+            mint_duelists(&mut erc721, accounts.bob);
+            let mut config = erc721.config();
+            config.protection_blocks = 1;
+            assert_eq!(erc721.set_config(config), Ok(()));
+            // Charlie and Django mint the weakest possible tokens 2 and 3.
+            for account in [accounts.charlie, accounts.django].iter() {
+                set_sender(*account);
+                assert_eq!(erc721.mint_with_seed([0x0; 32]), Ok(()));
+                ink_env::test::pop_execution_context();
+            }
+            // Tokens 0, 1 and 2 form a guild.
+            assert_eq!(erc721.create_guild(0), Ok(0));
+            for (token, owner) in [(1, accounts.bob), (2, accounts.charlie)].iter() {
+                assert_eq!(erc721.invite_to_guild(0, *token), Ok(()));
+                set_sender(*owner);
+                assert_eq!(erc721.join_guild(0, *token), Ok(()));
+                ink_env::test::pop_execution_context();
+            }
+            // Strangers cannot join a raid.
+            assert_eq!(erc721.raid(vec![0, 3], 1), Err(Error::NotAllowed));
+            // Token 3 is protected for its first block.
+            assert_eq!(erc721.raid(vec![0, 1, 2], 3), Err(Error::Protected));
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>()
+                .expect("Cannot advance block");
+            // Powers are Warded and cannot be raided, until they drop below Power.
+            erc721.set_rank(3, POWER);
+            assert_eq!(erc721.effects_of(3)[0].effect, StatusEffect::Warded);
+            assert_eq!(erc721.raid(vec![0, 1, 2], 3), Err(Error::Warded));
+            erc721.set_rank(3, 0);
+            assert_eq!(erc721.effects_of(3), vec![]);
+            // A raider whose owner is still cooling down holds the party back.
+            erc721.time_constrain(accounts.bob, 1);
+            assert_eq!(erc721.raid(vec![0, 1, 2], 3), Err(Error::NotAllowed));
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>()
+                .expect("Cannot advance block");
+            // The party of three overwhelms token 3.
+            assert_eq!(erc721.raid(vec![0, 1, 2], 3), Ok(()));
+            assert_eq!(erc721.losses_count(3), 3);
+            for token in 0..3 {
+                assert_eq!(erc721.victories_count(token), 1);
+                // Half of K at even ratings, shared three ways.
+                assert_eq!(erc721.rating_of(token), 1205);
+            }
+            assert_eq!(erc721.rating_of(3), 1185);
+            // Every raider's owner cools down.
+            for owner in [accounts.alice, accounts.bob, accounts.charlie].iter() {
+                assert!(erc721.is_ready(*owner) > 1);
+            }
+        }
+
+        #[ink::test]
+        fn status_effects_gate_combat() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // Create a new contract instance.
+            let mut erc721 = Erc721::new();
+            mint_duelists(&mut erc721, accounts.bob);
+            let mut config = erc721.config();
+            config.ascension_shield_blocks = 2;
+            config.wound_blocks = 1;
+            config.heavy_cooldown = 1;
+            assert_eq!(erc721.set_config(config), Ok(()));
+            // Bob's token ascends and is shielded for two blocks.
+            erc721.victories.insert(1, 4);
+            set_sender(accounts.bob);
+            assert_eq!(erc721.ascend(1), Ok(()));
+            ink_env::test::pop_execution_context();
+            assert_eq!(
+                erc721.effects_of(1),
+                vec![ActiveEffect {
+                    effect: StatusEffect::Shielded,
+                    expires_at: 2,
+                    stacks: 1,
+                }]
+            );
+            erc721.set_rank(0, PRINCIPALITY);
+            assert_eq!(erc721.improved_attack(0, 1), Err(Error::Shielded));
+            for _ in 0..2 {
+                ink_env::test::advance_block::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot advance block");
+            }
+            // Once the shield is gone a heavy hit wounds it for a block.
+            assert_eq!(erc721.improved_attack(0, 1), Ok(()));
+            assert_eq!(erc721.effects_of(1).len(), 1);
+            assert_eq!(erc721.effects_of(1)[0].effect, StatusEffect::Wounded);
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>()
+                .expect("Cannot advance block");
+            assert_eq!(erc721.effects_of(1), vec![]);
+            // A stunned token cannot strike, not even in a duel it asked for.
+            erc721.apply_effect(0, StatusEffect::Stunned, 5);
+            assert_eq!(erc721.improved_attack(0, 1), Err(Error::Stunned));
+            assert_eq!(erc721.challenge(0, 1, 10), Ok(()));
+            // Bob is done cooling down from his ascension.
+            erc721.time_constrain(accounts.bob, 0);
+            set_sender(accounts.bob);
+            assert_eq!(erc721.accept_challenge(0, 1), Err(Error::Stunned));
+            ink_env::test::pop_execution_context();
+        }
+
+        #[ink::test]
+        fn delay_angel_stuns_the_target_token() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // Create a new contract instance.
+            let mut erc721 = Erc721::new();
+            mint_duelists(&mut erc721, accounts.bob);
+            // Only Archangels can delay.
+            assert_eq!(erc721.delay_angel(0, 1), Err(Error::NotAllowed));
+            erc721.archangel.insert(0, true);
+            // Only existing tokens can be delayed.
+            assert_eq!(erc721.delay_angel(0, 7), Err(Error::TokenNotFound));
+            assert_eq!(erc721.delay_angel(0, 1), Ok(()));
+            assert_eq!(
+                erc721.effects_of(1),
+                vec![ActiveEffect {
+                    effect: StatusEffect::Stunned,
+                    expires_at: 7200,
+                    stacks: 1,
+                }]
+            );
+            // The stunned token cannot attack, but its owner is not on cooldown.
+            erc721.set_rank(1, PRINCIPALITY);
+            set_sender(accounts.bob);
+            assert_eq!(erc721.improved_attack(1, 0), Err(Error::Stunned));
+            ink_env::test::pop_execution_context();
+        }
+
+        #[ink::test]
+        fn effects_stack_up_to_their_limit() {
+            // Create a new contract instance.
+            let mut erc721 = Erc721::new();
+            assert_eq!(erc721.mint_with_seed([0x0; 32]), Ok(()));
+            let base = erc721.attack_score(0);
+            for _ in 0..4 {
+                erc721.apply_effect(0, StatusEffect::Blessed, 10);
+            }
+            // Blessed stacks three times at most.
+            assert_eq!(erc721.effects_of(0)[0].stacks, 3);
+            assert_eq!(erc721.attack_score(0), base + 3 * EFFECT_MODIFIER);
+            // Shielded does not stack at all.
+            erc721.apply_effect(0, StatusEffect::Shielded, 10);
+            erc721.apply_effect(0, StatusEffect::Shielded, 20);
+            assert_eq!(erc721.effects_of(0)[1].stacks, 1);
+            assert_eq!(erc721.effects_of(0)[1].expires_at, 20);
+        }
+
+        #[ink::test]
+        fn redeeming_a_loss_costs_victories_and_a_fee() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // Create a new contract instance.
+            let mut erc721 = Erc721::new();
+            mint_duelists(&mut erc721, accounts.bob);
+            assert_eq!(erc721.set_treasury(accounts.django), Ok(()));
+            assert_eq!(erc721.erase_loss(0), Err(Error::NoLosses));
+            erc721.losses.insert(0, 2);
+            erc721.victories.insert(0, 1);
+            let mut config = erc721.config();
+            config.redemption_fee = 10;
+            config.redemption_cap = 0;
+            assert_eq!(erc721.set_config(config.clone()), Ok(()));
+            assert_eq!(erc721.erase_loss(0), Err(Error::RedemptionCapReached));
+            config.redemption_cap = 1;
+            assert_eq!(erc721.set_config(config), Ok(()));
+            assert_eq!(erc721.erase_loss(0), Err(Error::NotEnoughVictories));
+            erc721.victories.insert(0, 3);
+            // The fee has to be paid in full.
+            set_sender_with_value(accounts.alice, 5);
+            assert_eq!(erc721.erase_loss(0), Err(Error::InsufficientFee));
+            ink_env::test::pop_execution_context();
+            // Overpaying Alice gets the change back.
+            let alice_before = balance_of(accounts.alice);
+            let django_before = balance_of(accounts.django);
+            set_sender_with_value(accounts.alice, 12);
+            set_balance(contract_id(), 12);
+            assert_eq!(erc721.erase_loss(0), Ok(()));
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc721.losses_count(0), 1);
+            assert_eq!(erc721.victories_count(0), 1);
+            assert_eq!(erc721.redemptions_of(0, 0), 1);
+            assert_eq!(balance_of(accounts.django), django_before + 10);
+            assert_eq!(balance_of(accounts.alice), alice_before + 2);
+            assert_eq!(balance_of(contract_id()), 0);
+        }
+
+        #[ink::test]
+        fn redeeming_a_loss_updates_the_leaderboard() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // Create a new contract instance.
+            let mut erc721 = Erc721::new();
+            mint_duelists(&mut erc721, accounts.bob);
+            erc721.victories.insert(0, 3);
+            erc721.victories.insert(1, 2);
+            erc721.losses.insert(0, 1);
+            erc721.update_leaderboard(0);
+            erc721.update_leaderboard(1);
+            assert_eq!(erc721.leaderboard(), vec![(0, 3), (1, 2)]);
+            // Spending two victories drops token 0 below token 1.
+            assert_eq!(erc721.erase_loss(0), Ok(()));
+            assert_eq!(erc721.leaderboard(), vec![(1, 2), (0, 1)]);
+        }
+
+        #[ink::test]
+        fn losing_tokens_fall_and_can_be_redeemed() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // Create a new contract instance.
+            let mut erc721 = Erc721::new();
+            mint_duelists(&mut erc721, accounts.bob);
+            let mut config = erc721.config();
+            config.fall_margin = 1;
+            config.redemption_quest = 2;
+            assert_eq!(erc721.set_config(config), Ok(()));
+            erc721.set_rank(1, POWER);
+            // One loss more than victories is within the margin.
+            erc721.add_loss(&1);
+            assert_eq!(erc721.faction_of(1), Faction::Heaven);
+            // The second one casts the token out and strips its ranks.
+            erc721.add_loss(&1);
+            assert_eq!(erc721.faction_of(1), Faction::Fallen);
+            assert_eq!(erc721.rank_of(1), 0);
+            assert_eq!(erc721.effects_of(1), vec![]);
+            assert_eq!(erc721.depth_of(1), 2);
+            assert_eq!(erc721.depth_of(0), 0);
+            set_sender(accounts.bob);
+            assert_eq!(erc721.ascend(1), Err(Error::Fallen));
+            // A Fallen token wounds once per circle it has descended.
+            assert_eq!(erc721.corrupt(1, 0), Ok(()));
+            assert_eq!(erc721.effects_of(0)[0].effect, StatusEffect::Wounded);
+            assert_eq!(erc721.effects_of(0)[0].stacks, 2);
+            // Redemption takes two victories after the fall.
+            assert_eq!(erc721.seek_redemption(1), Err(Error::QuestIncomplete));
+            erc721.add_loss(&1);
+            erc721.add_victory(&1);
+            erc721.add_victory(&1);
+            assert_eq!(erc721.quest_progress(1), Some(2));
+            assert_eq!(erc721.seek_redemption(1), Ok(()));
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc721.faction_of(1), Faction::Heaven);
+            // It comes back three losses to two victories, yet one more loss is still
+            // within the margin.
+            erc721.add_loss(&1);
+            assert_eq!(erc721.faction_of(1), Faction::Heaven);
+            erc721.add_loss(&1);
+            assert_eq!(erc721.faction_of(1), Faction::Fallen);
+        }
+
+        #[ink::test]
+        fn faction_war_pays_the_winning_side() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // Create a new contract instance.
+            let mut erc721 = Erc721::new();
+            mint_duelists(&mut erc721, accounts.bob);
+            set_sender_with_value(accounts.charlie, 1000);
+            erc721.fund_war_chest();
+            assert_eq!(erc721.mint_with_seed([0x0; 32]), Ok(()));
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc721.war_chest(), 1000);
+            // Alice's and Charlie's heavenly tokens beat Bob's Fallen one.
+            erc721.fallen.insert(1, 0);
+            assert_eq!(erc721.attack(0, 1), Ok(()));
+            erc721.score_war(2, 1);
+            assert_eq!(
+                erc721.faction_scores(),
+                FactionScores {
+                    heaven: 2,
+                    fallen: 0
+                }
+            );
+            assert_eq!(erc721.war_contribution(0, 0).heaven, 1);
+            // Rewards can only be claimed once the season is over.
+            assert_eq!(erc721.claim_war_reward(0, 0), Err(Error::WrongPhase));
+            // Heaven wins the season and half the chest is set aside for it.
+            assert_eq!(erc721.end_season(), Ok(()));
+            assert_eq!(erc721.war_winner(0), Some(Faction::Heaven));
+            assert_eq!(erc721.war_chest(), 500);
+            assert_eq!(erc721.faction_scores(), FactionScores::default());
+            // Alice claims her half, once.
+            let alice_before = balance_of(accounts.alice);
+            set_balance(contract_id(), 1000);
+            assert_eq!(erc721.claim_war_reward(0, 0), Ok(()));
+            assert_eq!(balance_of(accounts.alice), alice_before + 250);
+            assert_eq!(erc721.claim_war_reward(0, 0), Err(Error::AlreadyClaimed));
+            // Bob's token fought for the losing side.
+            set_sender(accounts.bob);
+            assert_eq!(erc721.claim_war_reward(0, 1), Err(Error::NoWarReward));
+            ink_env::test::pop_execution_context();
+            // Charlie burns his token before claiming, and Django's new token with the
+            // same id does not inherit its share.
+            set_sender(accounts.charlie);
+            assert_eq!(erc721.burn(2), Ok(()));
+            ink_env::test::pop_execution_context();
+            set_sender(accounts.django);
+            assert_eq!(erc721.mint_with_seed([0x0; 32]), Ok(()));
+            assert_eq!(erc721.claim_war_reward(0, 2), Err(Error::NoWarReward));
+            ink_env::test::pop_execution_context();
+            // The share nobody claimed goes back into the chest when the next season ends.
+            assert_eq!(erc721.end_season(), Ok(()));
+            assert_eq!(erc721.war_chest(), 750);
+            assert_eq!(erc721.claim_war_reward(0, 0), Err(Error::WrongPhase));
+        }
 
         #[ink::test]
-        fn mint_works() {
+        fn full_ranks_must_be_taken_by_dethroning() {
             let accounts =
                 ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
                     .expect("Cannot get accounts");
-               
             // Create a new contract instance.
             let mut erc721 = Erc721::new();
-            // Token 1 does not exists.
-            assert_eq!(erc721.owner_of(1), None);
-            // Alice does not owns tokens.
-            assert_eq!(erc721.balance_of(accounts.alice), 0);
-            // Create token Id 1.
-            assert_eq!(erc721.mint(), Ok(()));
-            assert_eq!(erc721.mint(), Err(Error::NotAllowed));
-            // Alice owns 1 token.
-            assert_eq!(erc721.balance_of(accounts.alice), 1);
-            assert_eq!(erc721.owner_of(0), Some(accounts.alice));
-            assert_eq!(erc721.token_owner.len(), 1);
-            //implemented to check the id assignment upon minting- passed!
-            //assert_eq!(erc721.owner_of(0), Some(accounts.alice));
-            //assert_eq!(erc721.owner_of(1), Some(accounts.alice));
-
+            mint_duelists(&mut erc721, accounts.bob);
+            let mut config = erc721.config();
+            config.seats = [1, 1, 1];
+            assert_eq!(erc721.set_config(config), Ok(()));
+            erc721.set_rank(1, SERAPHIM);
+            assert_eq!(erc721.rank_holders(SERAPHIM), vec![1]);
+            // The only Seraphim seat is taken.
+            erc721.victories.insert(0, 512);
+            assert_eq!(erc721.ascend(0), Err(Error::RankFull));
+            assert_eq!(erc721.dethrone(0, 1), Ok(()));
+            assert_eq!(erc721.rank_of(0), SERAPHIM);
+            assert_eq!(erc721.rank_of(1), CHERUBIM);
+            assert_eq!(erc721.rank_holders(SERAPHIM), vec![0]);
+            assert_eq!(erc721.rank_holders(CHERUBIM), vec![1]);
+            assert_eq!(erc721.rank_holders(0), vec![]);
         }
 
         #[ink::test]
-        fn mint_existing_should_fail() {
+        fn dethroned_holders_drop_to_a_free_seat() {
             let accounts =
                 ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
                     .expect("Cannot get accounts");
             // Create a new contract instance.
             let mut erc721 = Erc721::new();
-            // Create token Id 0.
-            assert_eq!(erc721.mint(), Ok(()));
-
-            // The first Transfer event takes place
-            assert_eq!(1, ink_env::test::recorded_events().count());
-            // Alice owns 1 token.
-            assert_eq!(erc721.balance_of(accounts.alice), 1);
-            // Alice owns token Id 1.
-            assert_eq!(erc721.owner_of(0), Some(accounts.alice));
-            //Should not allow caller to mint twice 
-            assert_eq!(erc721.mint(), Err(Error::NotAllowed));
+            mint_duelists(&mut erc721, accounts.bob);
+            set_sender(accounts.charlie);
+            assert_eq!(erc721.mint_with_seed([0x0; 32]), Ok(()));
+            ink_env::test::pop_execution_context();
+            let mut config = erc721.config();
+            config.seats = [1, 1, 1];
+            assert_eq!(erc721.set_config(config), Ok(()));
+            // Every limited rank is full.
+            erc721.set_rank(0, THRONE);
+            erc721.set_rank(1, SERAPHIM);
+            erc721.set_rank(2, CHERUBIM);
+            erc721.victories.insert(0, 512);
+            // The Seraphim cannot take the Cherubim's seat, so it drops to the Throne
+            // its challenger left.
+            assert_eq!(erc721.dethrone(0, 1), Ok(()));
+            assert_eq!(erc721.rank_of(0), SERAPHIM);
+            assert_eq!(erc721.rank_of(1), THRONE);
+            assert_eq!(erc721.rank_holders(CHERUBIM), vec![2]);
+            assert_eq!(erc721.rank_holders(THRONE), vec![1]);
+            // A demoted Cherubim finds no free Throne either.
+            erc721.set_rank(2, THRONE);
+            assert_eq!(erc721.rank_of(2), DOMINION);
+            assert_eq!(erc721.rank_holders(CHERUBIM), vec![]);
         }
 
         #[ink::test]
-        fn transfer_works() {
+        fn idle_tokens_decay() {
             let accounts =
                 ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
                     .expect("Cannot get accounts");
             // Create a new contract instance.
             let mut erc721 = Erc721::new();
-            // Create token Id 1 for Alice
-            assert_eq!(erc721.mint(), Ok(()));
-            // Alice owns token 1
-            assert_eq!(erc721.balance_of(accounts.alice), 1);
-            // Bob does not owns any token
-            assert_eq!(erc721.balance_of(accounts.bob), 0);
-            // The first Transfer event takes place
-            assert_eq!(1, ink_env::test::recorded_events().count());
-            // Alice transfers token 1 to Bob
-            assert_eq!(erc721.transfer(accounts.bob, 0), Ok(()));
-            // The second Transfer event takes place
-            assert_eq!(2, ink_env::test::recorded_events().count());
-            // Bob owns token 1
-            assert_eq!(erc721.balance_of(accounts.bob), 1);
+            mint_duelists(&mut erc721, accounts.bob);
+            let mut config = erc721.config();
+            config.idle_blocks = 2;
+            config.decay_period = 2;
+            config.decay_bounty = 10;
+            assert_eq!(erc721.set_config(config), Ok(()));
+            erc721.set_rank(1, POWER);
+            for _ in 0..5 {
+                ink_env::test::advance_block::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot advance block");
+            }
+            // The bounty is paid from a pool that holds less than it.
+            set_sender_with_value(accounts.django, 15);
+            erc721.fund_decay_pool();
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc721.decay_pool(), 15);
+            // Five idle blocks are two decay periods past the grace.
+            let charlie_before = balance_of(accounts.charlie);
+            set_balance(contract_id(), 15);
+            set_sender(accounts.charlie);
+            assert_eq!(erc721.apply_decay(vec![0, 1]), Ok(()));
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc721.rank_of(1), ARCHANGEL);
+            assert_eq!(balance_of(accounts.charlie), charlie_before + 15);
+            assert_eq!(erc721.decay_pool(), 0);
+            // The same idleness is not charged twice.
+            assert_eq!(erc721.apply_decay(vec![1]), Ok(()));
+            assert_eq!(erc721.rank_of(1), ARCHANGEL);
+            // Attacking marks a token active again.
+            assert_eq!(erc721.attack(0, 1), Ok(()));
+            assert_eq!(erc721.last_active(0), Some(5));
+            assert_eq!(erc721.last_active(1), Some(0));
         }
 
         #[ink::test]
-        fn invalid_transfer_should_fail() {
+        fn newcomers_are_protected_until_they_attack() {
             let accounts =
                 ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
                     .expect("Cannot get accounts");
             // Create a new contract instance.
             let mut erc721 = Erc721::new();
-            // Transfer token fails if it does not exists.
-            assert_eq!(erc721.transfer(accounts.bob, 2), Err(Error::TokenNotFound));
-            // Token Id 2 does not exists.
-            assert_eq!(erc721.owner_of(2), None);
-            // Create token Id 2.
-            assert_eq!(erc721.mint(), Ok(()));
-            // Alice owns 1 token.
-            assert_eq!(erc721.balance_of(accounts.alice), 1);
-           // Get contract address
-            let callee = ink_env::account_id::<ink_env::DefaultEnvironment>()
-                .unwrap_or([0x0; 32].into());
-            // Create call
-            let mut data =
-                ink_env::test::CallData::new(ink_env::call::Selector::new([0x00; 4])); // balance_of
-            data.push_arg(&accounts.bob);
-            // Push the new execution context to set Bob as caller
-            ink_env::test::push_execution_context::<ink_env::DefaultEnvironment>(
-                accounts.bob,
-                callee,
-                1000000,
-                1000000,
-                data,
-            );
-            // Bob cannot transfer not owned tokens.
-            assert_eq!(erc721.transfer(accounts.eve, 2), Err(Error::NotApproved));
+            mint_duelists(&mut erc721, accounts.bob);
+            let mut config = erc721.config();
+            config.protection_blocks = 10;
+            config.attack_cooldown = 1;
+            assert_eq!(erc721.set_config(config), Ok(()));
+            // Charlie and Django mint the weakest possible tokens 2 and 3.
+            for account in [accounts.charlie, accounts.django].iter() {
+                set_sender(*account);
+                assert_eq!(erc721.mint_with_seed([0x0; 32]), Ok(()));
+                ink_env::test::pop_execution_context();
+            }
+            assert_eq!(erc721.protected_until(2), Some(10));
+            assert_eq!(erc721.protected_until(0), None);
+            // Tokens 0 and 1 are guild mates, and token 0 is a Principality.
+            assert_eq!(erc721.create_guild(0), Ok(0));
+            assert_eq!(erc721.invite_to_guild(0, 1), Ok(()));
+            set_sender(accounts.bob);
+            assert_eq!(erc721.join_guild(0, 1), Ok(()));
+            ink_env::test::pop_execution_context();
+            erc721.set_rank(0, PRINCIPALITY);
+            // Nothing can target a newcomer.
+            assert_eq!(erc721.attack(0, 2), Err(Error::Protected));
+            assert_eq!(erc721.improved_attack(0, 2), Err(Error::Protected));
+            assert_eq!(erc721.gangel_bangel(0, 1, 2), Err(Error::Protected));
+            assert_eq!(erc721.delay_angel(0, 2), Err(Error::Protected));
+            // Attacking gives the protection up early.
+            set_sender(accounts.charlie);
+            assert_eq!(erc721.attack(2, 1), Ok(()));
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc721.protected_until(2), None);
+            assert_eq!(erc721.attack(0, 2), Ok(()));
+            // Otherwise it lasts until its blocks have passed.
+            for _ in 0..9 {
+                ink_env::test::advance_block::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot advance block");
+            }
+            assert_eq!(erc721.protected_until(3), Some(10));
+            assert_eq!(erc721.improved_attack(0, 3), Err(Error::Protected));
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>()
+                .expect("Cannot advance block");
+            assert_eq!(erc721.protected_until(3), None);
+            assert_eq!(erc721.improved_attack(0, 3), Ok(()));
         }
 
         #[ink::test]
-        fn approved_transfer_works() {
+        fn leagues_restrict_attacks_by_rank() {
             let accounts =
                 ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
                     .expect("Cannot get accounts");
             // Create a new contract instance.
             let mut erc721 = Erc721::new();
-            // Create token Id 1.
-            assert_eq!(erc721.mint(), Ok(()));
-            // Token Id 1 is owned by Alice.
-            assert_eq!(erc721.owner_of(0), Some(accounts.alice));
-            // Approve token Id 1 transfer for Bob on behalf of Alice.
-            assert_eq!(erc721.approve(accounts.bob, 0), Ok(()));
-            // Get contract address.
-            let callee = ink_env::account_id::<ink_env::DefaultEnvironment>()
-                .unwrap_or([0x0; 32].into());
-            // Create call
-            let mut data =
-                ink_env::test::CallData::new(ink_env::call::Selector::new([0x00; 4])); // balance_of
-            data.push_arg(&accounts.bob);
-            // Push the new execution context to set Bob as caller
-            ink_env::test::push_execution_context::<ink_env::DefaultEnvironment>(
-                accounts.bob,
-                callee,
-                1000000,
-                1000000,
-                data,
-            );
-            // Bob transfers token Id 1 from Alice to Eve.
-            assert_eq!(
-                erc721.transfer_from(accounts.alice, accounts.eve, 0),
-                Ok(())
-            );
-            // TokenId 3 is owned by Eve.
-            assert_eq!(erc721.owner_of(0), Some(accounts.eve));
-            // Alice does not owns tokens.
-            assert_eq!(erc721.balance_of(accounts.alice), 0);
-            // Bob does not owns tokens.
-            assert_eq!(erc721.balance_of(accounts.bob), 0);
-            // Eve owns 1 token.
-            assert_eq!(erc721.balance_of(accounts.eve), 1);
+            mint_duelists(&mut erc721, accounts.bob);
+            erc721.set_rank(0, VIRTUE);
+            let mut config = erc721.config();
+            config.league = League::Bracketed;
+            config.league_distance = 3;
+            assert_eq!(erc721.set_config(config.clone()), Ok(()));
+            // A Virtue is four tiers above an unranked token.
+            assert_eq!(erc721.can_attack(0, 1), Err(Error::OutOfLeague));
+            assert_eq!(erc721.can_attack(1, 0), Err(Error::OutOfLeague));
+            assert_eq!(erc721.attack(0, 1), Err(Error::OutOfLeague));
+            erc721.set_rank(1, ARCHANGEL);
+            assert_eq!(erc721.can_attack(0, 1), Ok(()));
+            assert_eq!(erc721.can_attack(0, 0), Err(Error::NotAllowed));
+            // Under the punitive rule the attack goes ahead but wins nothing, not even
+            // a point for Heaven against the Fallen token.
+            erc721.set_rank(1, 0);
+            erc721.fallen.insert(1, 0);
+            config.league = League::Punitive;
+            assert_eq!(erc721.set_config(config), Ok(()));
+            assert_eq!(erc721.can_attack(0, 1), Ok(()));
+            assert_eq!(erc721.attack(0, 1), Ok(()));
+            assert_eq!(erc721.victories_count(0), 0);
+            assert_eq!(erc721.losses_count(1), 0);
+            assert_eq!(erc721.rating_of(0), 1200 - 25);
+            assert_eq!(erc721.rating_of(1), 1200);
+            assert_eq!(erc721.battle_history(0, 0, 10), vec![]);
+            assert_eq!(erc721.faction_scores(), FactionScores::default());
+            assert_eq!(erc721.can_attack(0, 1), Err(Error::NotAllowed));
         }
 
         #[ink::test]
-        fn approved_for_all_works() {
+        fn queue_pairs_tokens_in_the_same_band() {
             let accounts =
                 ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
                     .expect("Cannot get accounts");
             // Create a new contract instance.
             let mut erc721 = Erc721::new();
-            // Create token Id 1.
-            assert_eq!(erc721.mint(), Ok(()));
-            // Create token Id 2.
-            assert_eq!(erc721.mint(), Ok(()));
-            // Alice owns 2 tokens.
-            assert_eq!(erc721.balance_of(accounts.alice), 2);
-            // Approve token Id 1 transfer for Bob on behalf of Alice.
-            assert_eq!(erc721.set_approval_for_all(accounts.bob, true), Ok(()));
-            // Bob is an approved operator for Alice
-            assert_eq!(
-                erc721.is_approved_for_all(accounts.alice, accounts.bob),
-                true
-            );
-            // Get contract address.
-            let callee = ink_env::account_id::<ink_env::DefaultEnvironment>()
-                .unwrap_or([0x0; 32].into());
-            // Create call
-            let mut data =
-                ink_env::test::CallData::new(ink_env::call::Selector::new([0x00; 4])); // balance_of
-            data.push_arg(&accounts.bob);
-            // Push the new execution context to set Bob as caller
-            ink_env::test::push_execution_context::<ink_env::DefaultEnvironment>(
-                accounts.bob,
-                callee,
-                1000000,
-                1000000,
-                data,
-            );
-            // Bob transfers token Id 1 from Alice to Eve.
-            assert_eq!(
-                erc721.transfer_from(accounts.alice, accounts.eve, 1),
-                Ok(())
-            );
-            // TokenId 1 is owned by Eve.
-            assert_eq!(erc721.owner_of(1), Some(accounts.eve));
-            // Alice owns 1 token.
-            assert_eq!(erc721.balance_of(accounts.alice), 1);
-            // Bob transfers token Id 2 from Alice to Eve.
-            assert_eq!(
-                erc721.transfer_from(accounts.alice, accounts.eve, 2),
-                Ok(())
-            );
-            // Bob does not owns tokens.
-            assert_eq!(erc721.balance_of(accounts.bob), 0);
-            // Eve owns 2 tokens.
-            assert_eq!(erc721.balance_of(accounts.eve), 2);
-            // Get back to the parent execution context.
+            mint_duelists(&mut erc721, accounts.bob);
+            set_sender(accounts.charlie);
+            assert_eq!(erc721.mint_with_seed([0x0; 32]), Ok(()));
             ink_env::test::pop_execution_context();
-            // Remove operator approval for Bob on behalf of Alice.
-            assert_eq!(erc721.set_approval_for_all(accounts.bob, false), Ok(()));
-            // Bob is not an approved operator for Alice.
+            // Charlie's token is rated far higher than the others.
+            erc721.ratings.insert(2, 1500);
+            assert_eq!(erc721.enter_queue(0), Ok(()));
+            assert_eq!(erc721.enter_queue(0), Err(Error::AlreadyQueued));
             assert_eq!(
-                erc721.is_approved_for_all(accounts.alice, accounts.bob),
-                false
+                erc721.queue_entry(0),
+                Some(QueueEntry {
+                    owner: accounts.alice,
+                    band: 12,
+                    entered_at: 0,
+                })
             );
+            set_sender(accounts.charlie);
+            assert_eq!(erc721.enter_queue(2), Ok(()));
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc721.waiting_in(15), Some(2));
+            // Bob's token shares Alice's band, so they fight straight away.
+            set_sender(accounts.bob);
+            assert_eq!(erc721.enter_queue(1), Ok(()));
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc721.queue_entry(0), None);
+            assert_eq!(erc721.waiting_in(12), None);
+            assert_eq!(erc721.victories_count(0), 1);
+            assert_eq!(erc721.losses_count(1), 1);
+            // Charlie gives up waiting.
+            set_sender(accounts.charlie);
+            assert_eq!(erc721.leave_queue(2), Ok(()));
+            assert_eq!(erc721.leave_queue(2), Err(Error::NotQueued));
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc721.waiting_in(15), None);
         }
 
         #[ink::test]
-        fn not_approved_transfer_should_fail() {
+        fn queue_rechecks_the_waiting_token() {
             let accounts =
                 ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
                     .expect("Cannot get accounts");
             // Create a new contract instance.
             let mut erc721 = Erc721::new();
-            // Create token Id 1.
-            assert_eq!(erc721.mint(), Ok(()));
-            // Alice owns 1 token.
-            assert_eq!(erc721.balance_of(accounts.alice), 1);
-            // Bob does not owns tokens.
-            assert_eq!(erc721.balance_of(accounts.bob), 0);
-            // Eve does not owns tokens.
-            assert_eq!(erc721.balance_of(accounts.eve), 0);
-            // Get contract address.
-            let callee = ink_env::account_id::<ink_env::DefaultEnvironment>()
-                .unwrap_or([0x0; 32].into());
-            // Create call
-            let mut data =
-                ink_env::test::CallData::new(ink_env::call::Selector::new([0x00; 4])); // balance_of
-            data.push_arg(&accounts.bob);
-            // Push the new execution context to set Eve as caller
-            ink_env::test::push_execution_context::<ink_env::DefaultEnvironment>(
-                accounts.eve,
-                callee,
-                1000000,
-                1000000,
-                data,
-            );
-            // Eve is not an approved operator by Alice.
-            assert_eq!(
-                erc721.transfer_from(accounts.alice, accounts.frank, 0),
-                Err(Error::NotApproved)
-            );
-            // Alice owns 1 token.
-            assert_eq!(erc721.balance_of(accounts.alice), 1);
-            // Bob does not owns tokens.
-            assert_eq!(erc721.balance_of(accounts.bob), 0);
-            // Eve does not owns tokens.
-            assert_eq!(erc721.balance_of(accounts.eve), 0);
+            mint_duelists(&mut erc721, accounts.bob);
+            set_sender(accounts.charlie);
+            assert_eq!(erc721.mint_with_seed([0x0; 32]), Ok(()));
+            ink_env::test::pop_execution_context();
+            // Alice's token is rated into another band while it waits.
+            assert_eq!(erc721.enter_queue(0), Ok(()));
+            erc721.ratings.insert(0, 1300);
+            set_sender(accounts.bob);
+            assert_eq!(erc721.enter_queue(1), Ok(()));
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc721.queue_entry(0), None);
+            assert_eq!(erc721.waiting_in(12), Some(1));
+            assert_eq!(erc721.victories_count(0), 0);
+            // A shielded token cannot queue, as the waiting token could not attack it.
+            erc721.apply_effect(2, StatusEffect::Shielded, 1);
+            set_sender(accounts.charlie);
+            assert_eq!(erc721.enter_queue(2), Err(Error::Shielded));
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc721.waiting_in(12), Some(1));
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>()
+                .expect("Cannot advance block");
+            // A waiting token that was stunned cannot attack, so it gives up its place.
+            erc721.apply_effect(1, StatusEffect::Stunned, 10);
+            set_sender(accounts.charlie);
+            assert_eq!(erc721.enter_queue(2), Ok(()));
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc721.queue_entry(1), None);
+            assert_eq!(erc721.waiting_in(12), Some(2));
+            assert_eq!(erc721.losses_count(2), 0);
+            // So does one whose owner is cooling down.
+            erc721.time_constrain(accounts.charlie, 10);
+            set_sender(accounts.django);
+            assert_eq!(erc721.mint_with_seed([0x0; 32]), Ok(()));
+            assert_eq!(erc721.enter_queue(3), Ok(()));
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc721.queue_entry(2), None);
+            assert_eq!(erc721.waiting_in(12), Some(3));
+            assert_eq!(erc721.losses_count(3), 0);
         }
 
         #[ink::test]
-        fn burn_works() {
+        fn leaderboard_refreshes_from_offered_tokens() {
             let accounts =
                 ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
                     .expect("Cannot get accounts");
             // Create a new contract instance.
             let mut erc721 = Erc721::new();
-            // Create token Id 1 for Alice
-            assert_eq!(erc721.mint(), Ok(()));
-            // Alice owns 1 token.
-            assert_eq!(erc721.balance_of(accounts.alice), 1);
-            // Alice owns token Id 1.
-            assert_eq!(erc721.owner_of(0), Some(accounts.alice));
-            // Destroy token Id 1.
-            assert_eq!(erc721.burn(0), Ok(()));
-            // Alice does not owns tokens.
-            assert_eq!(erc721.balance_of(accounts.alice), 0);
-            // Token Id 1 does not exists
-            assert_eq!(erc721.owner_of(1), None);
+            mint_duelists(&mut erc721, accounts.bob);
+            // Changing the board's rules sorts the listed tokens again.
+            let mut config = erc721.config();
+            config.standing = Standing::Rating;
+            config.leaderboard_size = 1;
+            assert_eq!(erc721.set_config(config.clone()), Ok(()));
+            assert_eq!(erc721.leaderboard(), vec![(0, 1200)]);
+            // Alice's token keeps its place after dropping below Bob's, until Bob's is offered.
+            erc721.ratings.insert(0, 1100);
+            erc721.update_leaderboard(0);
+            assert_eq!(erc721.leaderboard(), vec![(0, 1100)]);
+            erc721.refresh_leaderboard(vec![1]);
+            assert_eq!(erc721.leaderboard(), vec![(1, 1200)]);
+            // A bigger board has room for both once Alice's token is offered again.
+            config.leaderboard_size = 2;
+            assert_eq!(erc721.set_config(config), Ok(()));
+            assert_eq!(erc721.leaderboard(), vec![(1, 1200)]);
+            erc721.refresh_leaderboard(vec![0]);
+            assert_eq!(erc721.leaderboard(), vec![(1, 1200), (0, 1100)]);
         }
 
         #[ink::test]
-        fn burn_fails_token_not_found() {
+        fn burnt_history_is_not_inherited() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
             // Create a new contract instance.
             let mut erc721 = Erc721::new();
-            // Try burning a non existent token
-            assert_eq!(erc721.burn(1), Err(Error::TokenNotFound));
+            mint_duelists(&mut erc721, accounts.bob);
+            assert_eq!(erc721.attack(0, 1), Ok(()));
+            assert_eq!(erc721.battle_history(1, 0, 10).len(), 1);
+            set_sender(accounts.bob);
+            assert_eq!(erc721.burn(1), Ok(()));
+            ink_env::test::pop_execution_context();
+            // Charlie's new token reuses id 1 but starts with a clean history.
+            set_sender(accounts.charlie);
+            assert_eq!(erc721.mint_with_seed([0x0; 32]), Ok(()));
+            ink_env::test::pop_execution_context();
+            assert_eq!(erc721.owner_of(1), Some(accounts.charlie));
+            assert_eq!(erc721.battle_history(1, 0, 10), vec![]);
         }
 
         #[ink::test]
-        fn burn_fails_not_owner() {
+        fn tournaments_ignore_leagues() {
             let accounts =
                 ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
                     .expect("Cannot get accounts");
             // Create a new contract instance.
             let mut erc721 = Erc721::new();
-            // Create token Id 1 for Alice
-            assert_eq!(erc721.mint(), Ok(()));
-            // Try burning this token with a different account
-            set_sender(accounts.eve);
-            assert_eq!(erc721.burn(0), Err(Error::NotOwner));
+            mint_duelists(&mut erc721, accounts.bob);
+            let mut config = erc721.config();
+            config.league = League::Bracketed;
+            config.league_distance = 0;
+            assert_eq!(erc721.set_config(config.clone()), Ok(()));
+            erc721.set_rank(0, VIRTUE);
+            assert_eq!(erc721.attack(0, 1), Err(Error::OutOfLeague));
+            // Token 0 still meets token 1 in a bracketed tournament...
+            let rounds = [(0, League::Bracketed, 1), (1, League::Punitive, 2)];
+            for (id, league, deadline) in rounds.iter() {
+                config.league = *league;
+                assert_eq!(erc721.set_config(config.clone()), Ok(()));
+                assert_eq!(erc721.create_tournament(0, 2, *deadline), Ok(*id));
+                for (token, owner) in [accounts.alice, accounts.bob].iter().enumerate() {
+                    set_sender_with_value(*owner, 0);
+                    assert_eq!(erc721.enter_tournament(*id, token as TokenId), Ok(()));
+                    ink_env::test::pop_execution_context();
+                }
+                ink_env::test::advance_block::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot advance block");
+                assert_eq!(erc721.seed_tournament(*id), Ok(()));
+                assert_eq!(erc721.play_round(*id), Ok(()));
+            }
+            // ...and wins both times, in a punitive one without a penalty.
+            assert_eq!(erc721.victories_count(0), 2);
+            assert_eq!(erc721.losses_count(1), 2);
+            assert!(erc721.rating_of(0) > 1200);
+        }
+
+        fn contract_id() -> AccountId {
+            ink_env::account_id::<ink_env::DefaultEnvironment>()
+                .unwrap_or([0x0; 32].into())
+        }
+
+        fn balance_of(account: AccountId) -> Balance {
+            test::get_account_balance::<ink_env::DefaultEnvironment>(account)
+                .expect("Cannot get account balance")
+        }
+
+        fn set_balance(account: AccountId, balance: Balance) {
+            test::set_account_balance::<ink_env::DefaultEnvironment>(account, balance)
+                .expect("Cannot set account balance")
+        }
+
+        /// Mints token 0 for Alice with maximum might and grace and token 1
+        /// for `defender` with maximum resilience, both in the Light choir,
+        /// with newcomer protection turned off.
+        fn mint_duelists(erc721: &mut Erc721, defender: AccountId) {
+            let mut config = erc721.config();
+            config.protection_blocks = 0;
+            assert_eq!(erc721.set_config(config), Ok(()));
+            let mut seed = [0x0; 32];
+            seed[..5].copy_from_slice(&[15, 15, 0, 0, 1]);
+            assert_eq!(erc721.mint_with_seed(seed), Ok(()));
+            set_sender(defender);
+            seed[..5].copy_from_slice(&[0, 0, 15, 0, 1]);
+            assert_eq!(erc721.mint_with_seed(seed), Ok(()));
+            ink_env::test::pop_execution_context();
+        }
+
+        /// Calls as `sender` with `value` attached, moving it from the sender's balance
+        /// into the contract's as the chain does for payable messages.
+        fn set_sender_paying(sender: AccountId, value: Balance) {
+            set_balance(sender, balance_of(sender) - value);
+            set_balance(contract_id(), balance_of(contract_id()) + value);
+            set_sender_with_value(sender, value);
         }
 
         fn set_sender(sender: AccountId) {
+            set_sender_with_value(sender, 1000000);
+        }
+
+        fn set_sender_with_value(sender: AccountId, value: Balance) {
             let callee = ink_env::account_id::<ink_env::DefaultEnvironment>()
                 .unwrap_or([0x0; 32].into());
             test::push_execution_context::<Environment>(
                 sender,
                 callee,
                 1000000,
-                1000000,
+                value,
                 test::CallData::new(call::Selector::new([0x00; 4])), // dummy
             );
         }